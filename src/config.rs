@@ -0,0 +1,46 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// User configuration lives in plain text files under $XDG_CONFIG_HOME/cosmic-dust
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".config")))
+        .map(|dir| dir.join("cosmic-dust"))
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(PathBuf::from).filter(|home| home.is_absolute())
+}
+
+// Expand a leading "~" to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+// Read a config file, skipping blank lines and "#" comments
+pub fn read_lines(file_name: &str, verbose: bool) -> Vec<String> {
+    let Some(path) = config_dir().map(|dir| dir.join(file_name)) else {
+        return Vec::new();
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => {
+            if verbose {
+                println!("Loaded config file {:?}", path);
+            }
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect()
+        }
+        Err(_) => Vec::new(),
+    }
+}
@@ -1,6 +1,16 @@
+use crate::mime::MimeDatabase;
+use crate::mountinfo::{read_mountinfo, MountInfo};
+use crate::network::NetworkFilesystems;
+use crate::reclaim::{remove_nested, ReclaimRules, ReclaimableEntry};
+use crate::swaps::{read_swaps, SwapKind};
+use crate::timeout::run_with_timeout;
+use crate::tree::ScanTree;
+use crate::user_dirs::special_folders;
 use jwalk::{Parallelism, WalkDir};
-use std::path::PathBuf;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use std::fs;
 use tokio::task::spawn_blocking;
 
@@ -9,23 +19,94 @@ pub struct FileEntry {
     pub path: PathBuf,
     pub size: u64,
     pub is_dir: bool,
+    pub modified: Option<SystemTime>,
+    pub reclaimable: Option<String>,
+    // An active swap file, sized by the kernel rather than by what's in it
+    pub swap: bool,
+    // Themed icons from the MIME type or XDG special folder, most specific first. Empty for the
+    // plain folder and file icons.
+    pub icons: Vec<String>,
 }
 
-pub async fn crawl_files(mount_point: String, verbose: bool) -> Vec<FileEntry> {
+#[derive(Debug, Clone)]
+pub struct CrawlResult {
+    pub entries: Vec<FileEntry>,
+    pub reclaimable: Vec<ReclaimableEntry>,
+    pub tree: Arc<ScanTree>,
+    // The crawl root didn't answer in time, usually a dead network mount
+    pub not_responding: bool,
+}
+
+// Network shares mounted somewhere below the crawl root. The root itself was checked already,
+// these get the same check before the walk steps into them.
+fn network_mounts_below(root: &Path, mounts: &[MountInfo], network_filesystems: &NetworkFilesystems) -> HashSet<PathBuf> {
+    mounts
+        .iter()
+        .filter(|mount| network_filesystems.icon_for(&mount.fs_type).is_some())
+        .map(|mount| PathBuf::from(&mount.mount_point))
+        .filter(|mount_point| mount_point != root && mount_point.starts_with(root))
+        .collect()
+}
+
+pub async fn crawl_files(mount_point: String, verbose: bool) -> CrawlResult {
     let root = PathBuf::from(&mount_point);
 
+    // A hung mount would block the walk forever, make sure the root answers first
+    let check_root = root.clone();
+    let key = format!("metadata {}", mount_point);
+    if run_with_timeout(key, move || fs::metadata(check_root).is_ok()).is_none() {
+        if verbose {
+            println!("{} is not responding, skipping crawl", mount_point);
+        }
+        return CrawlResult {
+            entries: Vec::new(),
+            reclaimable: Vec::new(),
+            tree: Arc::new(ScanTree::new(root)),
+            not_responding: true,
+        };
+    }
+
+    let network_mounts = network_mounts_below(&root, &read_mountinfo(), &NetworkFilesystems::load(verbose));
+
+    let crawl_root = root.clone();
     spawn_blocking(move || {
-        let mut dir_sizes: std::collections::HashMap<PathBuf, u64> = std::collections::HashMap::new();
-        let mut top_level_entries: Vec<FileEntry> = Vec::new();
+        let mut tree = ScanTree::new(root.clone());
+        // Directory path -> tree node, jwalk always yields a directory before its contents
+        let mut dir_nodes: HashMap<PathBuf, usize> = HashMap::from([(root.clone(), 0)]);
+        let mut top_level_entries: Vec<(usize, FileEntry)> = Vec::new();
+        let rules = ReclaimRules::load(verbose);
+        let mut reclaimable: Vec<(usize, ReclaimableEntry)> = Vec::new();
+        let swap_files: Vec<PathBuf> = read_swaps()
+            .into_iter()
+            .filter(|area| area.kind == SwapKind::File)
+            .map(|area| area.path)
+            .collect();
+        let mime_database = MimeDatabase::shared();
+        let special_folders = special_folders();
 
         for entry in WalkDir::new(&root)
             .follow_links(false)
-            .process_read_dir(|_depth, path, _read_dir_state, children| {
+            .process_read_dir(move |_depth, path, _read_dir_state, children| {
                 // Iterate over tree results and skip any folder we can't access
                 children.retain(|child_result| {
                     if let Ok(dir_entry) = child_result {
+                        if !dir_entry.file_type.is_dir() {
+                            return true;
+                        }
+                        let child = dir_entry.path();
+                        // A dead share would hang the walk on its first stat, check it the way the root was
+                        if network_mounts.contains(&child) {
+                            let key = format!("metadata {}", child.display());
+                            let check_child = child.clone();
+                            if run_with_timeout(key, move || fs::metadata(check_child).is_ok()) != Some(true) {
+                                if verbose {
+                                    println!("{} is not responding, skipping it", child.display());
+                                }
+                                return false;
+                            }
+                        }
                         // If metadata fails (permission denied, etc.), skip descending into this folder
-                        if dir_entry.file_type.is_dir() && fs::metadata(dir_entry.path()).is_err() {
+                        if fs::metadata(&child).is_err() {
                             return false;
                         }
                     }
@@ -64,55 +145,125 @@ pub async fn crawl_files(mount_point: String, verbose: bool) -> Vec<FileEntry> {
             let size = metadata.len();
             let is_dir = metadata.is_dir();
 
-            if is_dir {
-                dir_sizes.entry(path.clone()).or_insert(0);
+            let Some(&parent) = path.parent().and_then(|parent| dir_nodes.get(parent)) else {
+                continue;
+            };
+            let node = tree.push(
+                parent,
+                entry.file_name().to_os_string(),
+                if is_dir { 0 } else { size },
+                is_dir,
+                metadata.modified().ok(),
+            );
+
+            let reclaim_match = if is_dir {
+                rules.matches(&path).map(str::to_string)
             } else {
-                let mut current = path.clone();
-                loop {
-                    *dir_sizes.entry(current.clone()).or_insert(0) += size;
-                    if let Some(parent) = current.parent() {
-                        if parent == root {
-                            break;
-                        }
-                        current = parent.to_path_buf();
-                    } else {
-                        break;
-                    }
-                }
+                None
+            };
+
+            if let Some(description) = &reclaim_match {
+                reclaimable.push((node, ReclaimableEntry {
+                    path: path.clone(),
+                    size: 0,
+                    description: description.clone(),
+                }));
+            }
+
+            if is_dir {
+                dir_nodes.insert(path.clone(), node);
             }
 
             // Collect top-level items on the fly
             if depth == 1 {
+                let swap = !is_dir && swap_files.contains(&path);
+                let icons = if is_dir {
+                    special_folders.iter()
+                        .filter(|(folder, _)| *folder == path)
+                        .map(|(_, icon)| icon.to_string())
+                        .collect()
+                } else if swap {
+                    Vec::new()
+                } else {
+                    mime_database.mime_for_file(&path, metadata.file_type())
+                        .map(|mime| mime_database.icon_names(&mime))
+                        .unwrap_or_default()
+                };
                 let entry = FileEntry {
                     path,
                     size: if is_dir { 0 } else { size },
                     is_dir,
+                    modified: metadata.modified().ok(),
+                    reclaimable: reclaim_match,
+                    swap,
+                    icons,
                 };
                 if verbose {
                     println!("Top-level discovered: {:?}", entry);
                 }
-                top_level_entries.push(entry);
+                top_level_entries.push((node, entry));
             }
         }
 
-        // Finalize top-level folder sizes from aggregation
-        for entry in &mut top_level_entries {
-            if entry.is_dir {
-                entry.size = *dir_sizes.get(&entry.path).unwrap_or(&0);
-            }
-        }
+        // Finalize folder sizes from aggregation
+        tree.finalize_sizes();
+        let top_level_entries: Vec<FileEntry> = top_level_entries
+            .into_iter()
+            .map(|(node, entry)| FileEntry { size: tree.nodes[node].size, ..entry })
+            .collect();
+
+        let reclaimable = remove_nested(
+            reclaimable
+                .into_iter()
+                .map(|(node, entry)| ReclaimableEntry { size: tree.nodes[node].size, ..entry })
+                .collect(),
+        );
 
         if verbose {
             println!("Total top-level entries for {}: {}", mount_point, top_level_entries.len());
+            println!("Potentially reclaimable locations for {}: {}", mount_point, reclaimable.len());
         }
 
-        top_level_entries
+        CrawlResult {
+            entries: top_level_entries,
+            reclaimable,
+            tree: Arc::new(tree),
+            not_responding: false,
+        }
     })
         .await
         .unwrap_or_else(|e| {
             if verbose {
                 println!("Crawl failed: {:?}", e);
             }
-            Vec::new()
+            CrawlResult {
+                entries: Vec::new(),
+                reclaimable: Vec::new(),
+                tree: Arc::new(ScanTree::new(crawl_root)),
+                not_responding: false,
+            }
         })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mountinfo::parse_mountinfo;
+
+    #[test]
+    fn finds_network_shares_mounted_below_the_root() {
+        let mounts = parse_mountinfo(
+            "22 1 8:2 / / rw,relatime - ext4 /dev/sda2 rw\n\
+             40 22 0:50 / /home rw,relatime - ext4 /dev/sda3 rw\n\
+             41 40 0:51 / /home/user/nas rw,relatime - nfs4 server:/export rw\n\
+             42 22 0:52 / /mnt/share rw,relatime - cifs //server/share rw\n\
+             43 40 0:53 / /home2/remote rw,relatime - fuse.sshfs host: rw\n",
+        );
+        let network_filesystems = NetworkFilesystems::load(false);
+        let below_home = network_mounts_below(Path::new("/home"), &mounts, &network_filesystems);
+        assert_eq!(below_home, HashSet::from([PathBuf::from("/home/user/nas")]));
+        assert_eq!(network_mounts_below(Path::new("/"), &mounts, &network_filesystems).len(), 3);
+        // The crawl root was already checked on its own
+        assert!(network_mounts_below(Path::new("/mnt/share"), &mounts, &network_filesystems).is_empty());
+    }
+}
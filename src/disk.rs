@@ -1,14 +1,105 @@
-use udev::Enumerator;
+use crate::mountinfo::{read_mountinfo, MountInfo};
+use crate::network::NetworkFilesystems;
+use crate::swaps::{read_swaps, SwapArea, SwapKind};
+use crate::sysfs::{self, SYSFS_ROOT};
+use crate::timeout::{run_with_timeout, Pending, IO_TIMEOUT};
+use udev::{Device, Enumerator};
+use std::collections::HashMap;
 use std::fs;
-use nix::sys::statvfs::statvfs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use nix::sys::stat::{major, minor};
+use nix::sys::statvfs::{statvfs, Statvfs};
+use nix::libc::dev_t;
 
 #[derive(Debug, Clone)]
 pub struct DiskInfo {
+    pub device: String,
     pub model: String,
     pub total_space: u64,
     pub partitions: Vec<PartitionInfo>,
     pub is_cdrom: bool,
     pub icon_name: String,
+    // LUKS containers, LVM volumes, md arrays and other devices stacked on this disk's partitions
+    pub layers: Vec<BlockLayer>,
+    // Partitions holding a filesystem that isn't mounted anywhere
+    pub unmounted: Vec<UnmountedFilesystem>,
+    // Active swap partitions, they have no mount point
+    pub swaps: Vec<SwapArea>,
+    // Size of the whole device, total_space only counts mounted filesystems
+    pub capacity: u64,
+    // Partitions and free space in on-disk order
+    pub layout: Vec<LayoutSegment>,
+    // Set for loop devices: the disk image or snap they expose
+    pub backing_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Mounted,
+    Unmounted,
+    Swap,
+    // Holds LUKS, LVM or md
+    Stacked,
+    Other,
+    Free,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutSegment {
+    // Kernel name, empty for free space
+    pub name: String,
+    pub kind: SegmentKind,
+    pub start: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnmountedFilesystem {
+    pub device: String,
+    pub file_system: String,
+    pub label: Option<String>,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerKind {
+    Luks,
+    VolumeGroup,
+    LogicalVolume,
+    Raid,
+    DeviceMapper,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockLayer {
+    pub kind: LayerKind,
+    pub name: String,
+    pub device: String,
+    pub filesystem: Option<PartitionInfo>,
+    // A filesystem on the layer that nobody has mounted, e.g. a logical volume inside LUKS
+    pub unmounted: Option<UnmountedFilesystem>,
+    // Active swap on the layer, e.g. a swap volume in an encrypted volume group
+    pub swap: Option<SwapArea>,
+    pub children: Vec<BlockLayer>,
+    // Devices spanning several disks are expanded under their first member only
+    pub shown_elsewhere: bool,
+}
+
+impl DiskInfo {
+    // Every mounted filesystem on this disk, including those on stacked volumes
+    pub fn filesystems(&self) -> Vec<&PartitionInfo> {
+        fn collect<'a>(layers: &'a [BlockLayer], out: &mut Vec<&'a PartitionInfo>) {
+            for layer in layers {
+                out.extend(layer.filesystem.as_ref());
+                collect(&layer.children, out);
+            }
+        }
+
+        let mut filesystems: Vec<&PartitionInfo> = self.partitions.iter().collect();
+        collect(&self.layers, &mut filesystems);
+        filesystems
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -17,7 +108,40 @@ pub struct PartitionInfo {
     pub mount_point: String,
     pub total_space: u64,
     pub used_space: u64,
+    // Free space usable without root privileges
+    pub available_space: u64,
+    // Free space held back for root, typically 5% on ext4
+    pub reserved_space: u64,
+    // Zero on filesystems that allocate inodes dynamically, like btrfs
+    pub total_inodes: u64,
+    pub used_inodes: u64,
     pub file_system: String,
+    // Bind mounts and other subvolumes of the same filesystem, each can be scanned on its own
+    pub other_mount_points: Vec<String>,
+    pub label: Option<String>,
+    pub uuid: Option<String>,
+    // Name stored in the GPT partition entry, independent of the filesystem label
+    pub part_label: Option<String>,
+    // Options of the primary mount, e.g. ["rw", "noatime", "compress=zstd:3"]
+    pub mount_options: Vec<String>,
+    pub read_only: bool,
+    // False when statvfs didn't answer in time, e.g. a stuck FUSE daemon
+    pub responding: bool,
+}
+
+// Warn once this share of a filesystem's inodes is in use
+const INODE_WARNING_PERCENT: u64 = 90;
+
+impl PartitionInfo {
+    pub fn inodes_nearly_full(&self) -> bool {
+        self.total_inodes > 0 && self.used_inodes * 100 >= self.total_inodes * INODE_WARNING_PERCENT
+    }
+
+    pub fn mount_points(&self) -> Vec<&str> {
+        std::iter::once(self.mount_point.as_str())
+            .chain(self.other_mount_points.iter().map(String::as_str))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,11 +150,38 @@ pub enum Drive {
     Network(NetworkDrive),
 }
 
+impl Drive {
+    // Stable identity across rescans
+    pub fn key(&self) -> &str {
+        match self {
+            Drive::Local(disk) => &disk.device,
+            Drive::Network(net) => &net.mount_point,
+        }
+    }
+
+    pub fn mount_points(&self) -> Vec<&str> {
+        match self {
+            Drive::Local(disk) => disk.filesystems().into_iter().flat_map(|p| p.mount_points()).collect(),
+            Drive::Network(net) => std::iter::once(net.mount_point.as_str())
+                .chain(net.other_mount_points.iter().map(String::as_str))
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct NetworkDrive {
     pub mount_point: String,
+    // Remote location, e.g. server:/export or //server/share
+    pub source: String,
+    pub file_system: String,
+    pub icon_name: String,
     pub used_space: u64,
     pub total_space: u64,
+    // The same export mounted again elsewhere
+    pub other_mount_points: Vec<String>,
+    // False when statvfs didn't answer in time, usually a dead server
+    pub responding: bool,
 }
 
 pub async fn scan_disks() -> Vec<Drive> {
@@ -43,18 +194,26 @@ pub async fn scan_disks() -> Vec<Drive> {
 
     let devices: Vec<_> = enumerator.scan_devices().expect("Failed to scan udev devices").collect();
     let mut root_map: Vec<(String, Vec<PartitionInfo>, bool, String)> = Vec::new();
+    let mut unmounted: Vec<(String, UnmountedFilesystem)> = Vec::new();
+    let mut swaps: Vec<(String, SwapArea)> = Vec::new();
+    let swap_partitions: Vec<SwapArea> = read_swaps()
+        .into_iter()
+        .filter(|area| area.kind == SwapKind::Partition)
+        .map(|area| SwapArea { path: fs::canonicalize(&area.path).unwrap_or(area.path.clone()), ..area })
+        .collect();
 
-    let mounts = fs::read_to_string("/proc/mounts").expect("Failed to read /proc/mounts");
-    let mounted: Vec<(String, String, String)> = mounts
-        .lines()
-        .filter_map(|line| {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 3 && parts[0].starts_with("/dev/") {
-                let mp = parts[1].replace("\\040", " ");
-                Some((parts[0].to_string(), mp, parts[2].to_string()))
-            } else {
-                None
+    let mount_table = read_mountinfo();
+    let mounted: Vec<MountInfo> = mount_table
+        .iter()
+        .cloned()
+        .map(|mut mount| {
+            // Resolve /dev/mapper/* and other symlinks to the kernel device node udev reports
+            if mount.source.starts_with("/dev/") {
+                if let Ok(dev) = fs::canonicalize(&mount.source) {
+                    mount.source = dev.to_string_lossy().to_string();
+                }
             }
+            mount
         })
         .collect();
 
@@ -70,12 +229,38 @@ pub async fn scan_disks() -> Vec<Drive> {
                 continue;
             }
 
-            let mount_info = mounted.iter().find(|(dev, _, _)| dev == &dev_path);
-            let (mount_point, file_system) = match mount_info {
-                Some((_, mp, fs)) => (mp.clone(), fs.clone()),
+            // Stacked devices are shown under the disks they're built on
+            let sysname = device.sysname().to_string_lossy().to_string();
+            if !sysfs::slaves(Path::new(SYSFS_ROOT), &sysname).is_empty() {
+                if verbose {
+                    println!("Deferring {} - stacked on other block devices", dev_path);
+                }
+                continue;
+            }
+
+            let mounts = mounts_of(&mounted, device.devnum(), &dev_path);
+            let (mount_point, file_system) = match mounts.first() {
+                Some(mount) => (mount.mount_point.clone(), mount.fs_type.clone()),
                 None => {
-                    if verbose {
-                        println!("Skipping {} - not mounted", dev_path);
+                    if let Some(area) = swap_partitions.iter().find(|area| area.path == Path::new(&dev_path)) {
+                        if verbose {
+                            println!("Found active swap on {}", dev_path);
+                        }
+                        swaps.push((root_disk(device, &dev_path), SwapArea { path: PathBuf::from(&dev_path), ..area.clone() }));
+                        continue;
+                    }
+                    match unmounted_filesystem(device, &dev_path) {
+                        Some(filesystem) => {
+                            if verbose {
+                                println!("Found unmounted {} filesystem on {}", filesystem.file_system, dev_path);
+                            }
+                            unmounted.push((root_disk(device, &dev_path), filesystem));
+                        }
+                        None => {
+                            if verbose {
+                                println!("Skipping {} - not mounted", dev_path);
+                            }
+                        }
                     }
                     continue;
                 }
@@ -89,79 +274,12 @@ pub async fn scan_disks() -> Vec<Drive> {
             }
 
             let is_cdrom = dev_path.contains("sr") || dev_path.contains("cdrom");
-            let is_root = if dev_path.contains("nvme") {
-                !dev_path.contains('p')
-            } else if is_cdrom {
-                true
-            } else {
-                !dev_path.chars().last().unwrap_or(' ').is_digit(10)
-            };
+            let root_dev = root_disk(device, &dev_path);
 
-            let root_dev = if is_root {
-                dev_path.clone()
-            } else if dev_path.contains("nvme") {
-                dev_path.split('p').next().unwrap().to_string()
-            } else {
-                format!("/dev/{}", dev_path.split('/').last().unwrap().chars().take_while(|c| !c.is_digit(10)).collect::<String>())
-            };
+            let space = filesystem_space(device, &dev_path, &mount_point, is_cdrom, verbose);
+            let icon_name = drive_icon(device, is_cdrom);
 
-            let (total_space, used_space) = if is_cdrom {
-                match statvfs(mount_point.as_str()) {
-                    Ok(stat) => {
-                        let block_size = stat.block_size() as u64;
-                        let total = stat.blocks() * block_size;
-                        (total, total)
-                    }
-                    Err(_) => {
-                        let size_str = device.property_value("SIZE").map(|s| s.to_string_lossy().to_string()).unwrap_or("0".to_string());
-                        let size = size_str.parse::<u64>().unwrap_or(0) * 512;
-                        if verbose {
-                            println!("CDROM fallback for {}: SIZE={}", dev_path, size);
-                        }
-                        (size, size)
-                    }
-                }
-            } else {
-                match statvfs(mount_point.as_str()) {
-                    Ok(stat) => {
-                        let block_size = stat.block_size() as u64;
-                        let total = stat.blocks() * block_size;
-                        let free = stat.blocks_free() * block_size;
-                        (total, total - free)
-                    }
-                    Err(e) => {
-                        if verbose {
-                            println!("Failed to statvfs {} ({}): {:?}", dev_path, mount_point, e);
-                        }
-                        (0, 0)
-                    }
-                }
-            };
-
-            let icon_name = if is_cdrom {
-                "drive-optical"
-            } else {
-                let media = device.property_value("ID_DRIVE_MEDIA").map(|s| s.to_string_lossy().to_string());
-                match media.as_deref() {
-                    Some("solidstate") => "drive-harddisk-solidstate",
-                    Some("disk") => "drive-harddisk",
-                    Some("flash") | Some("usb") => "drive-removable-media",
-                    Some("floppy") => "drive-floppy",
-                    _ => if device.property_value("ID_BUS").map_or(false, |b| b == "usb") {
-                        "drive-removable-media"
-                    } else {
-                        "drive-harddisk"
-                    }
-                }
-            };
-
-            let partition = PartitionInfo {
-                device: dev_path.clone(),
-                mount_point,
-                total_space,
-                used_space,
-                file_system: file_system.clone(),
-            };
+            let partition = partition_info(Some(device), dev_path.clone(), &mounts, space);
 
             if verbose {
                 println!("udev saw: {} (fs: {}) -> root: {} (icon: {})", dev_path, file_system, root_dev, icon_name);
@@ -178,8 +296,48 @@ pub async fn scan_disks() -> Vec<Drive> {
         println!("udev found {} root devices with mounted partitions", root_map.len());
     }
 
+    // Disks whose only content is stacked volumes (e.g. one big LUKS partition) still need listing
+    for device in &devices {
+        let sysname = device.sysname().to_string_lossy().to_string();
+        if sysfs::holders(Path::new(SYSFS_ROOT), &sysname).is_empty() || !sysfs::slaves(Path::new(SYSFS_ROOT), &sysname).is_empty() {
+            continue;
+        }
+        if let Some(devnode) = device.devnode() {
+            let dev_path = devnode.to_string_lossy().to_string();
+            let root_dev = root_disk(device, &dev_path);
+            if !root_map.iter().any(|(root, _, _, _)| root == &root_dev) {
+                if verbose {
+                    println!("udev saw stacked volumes on {} -> root: {}", dev_path, root_dev);
+                }
+                root_map.push((root_dev, Vec::new(), false, drive_icon(device, false).to_string()));
+            }
+        }
+    }
+
+    // Same for disks where nothing is mounted yet, or that only hold swap
+    let other_roots = unmounted.iter().map(|(root, _)| root).chain(swaps.iter().map(|(root, _)| root));
+    for root_dev in other_roots {
+        if !root_map.iter().any(|(root, _, _, _)| root == root_dev) {
+            let icon_name = devices
+                .iter()
+                .find(|device| device.devnode().is_some_and(|node| node.to_string_lossy() == root_dev.as_str()))
+                .map_or("drive-harddisk", |device| drive_icon(device, false));
+            root_map.push((root_dev.clone(), Vec::new(), false, icon_name.to_string()));
+        }
+    }
+
+    let devices_by_name: HashMap<String, &Device> = devices
+        .iter()
+        .map(|device| (device.sysname().to_string_lossy().to_string(), device))
+        .collect();
+
     for (root_dev, partitions, is_cdrom, mut icon_name) in root_map {
-        let total_space = partitions.iter().map(|p| p.total_space).sum();
+        let disk_name = Path::new(&root_dev).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let layers: Vec<BlockLayer> = std::iter::once(disk_name.clone())
+            .chain(sysfs::partitions(Path::new(SYSFS_ROOT), &disk_name))
+            .flat_map(|name| block_layers(&name, &devices_by_name, &mounted, &swap_partitions, verbose))
+            .collect();
+
         let mut model = "Unknown Model".to_string();
 
         for device in &devices {
@@ -221,56 +379,136 @@ pub async fn scan_disks() -> Vec<Drive> {
             }
         }
 
+        // Loop devices are named after the image they expose instead of a drive model
+        let backing_file = sysfs::loop_backing_file(Path::new(SYSFS_ROOT), &disk_name);
+        if let Some(file) = &backing_file {
+            model = file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| file.to_string_lossy().to_string());
+            let is_snap = partitions.iter().any(|partition| partition.file_system == "squashfs");
+            icon_name = if is_snap { "package-x-generic" } else { "application-x-cd-image" }.to_string();
+            if verbose {
+                println!("Loop device {} is backed by {}", root_dev, file.display());
+            }
+        }
+
         if verbose {
-            println!("Disk {} has {} partitions", root_dev, partitions.len());
+            println!("Disk {} has {} partitions and {} stacked volumes", root_dev, partitions.len(), layers.len());
         }
-        drives.push(Drive::Local(DiskInfo {
+        let (capacity, layout) = if is_cdrom {
+            (0, Vec::new())
+        } else {
+            disk_layout(Path::new(SYSFS_ROOT), &disk_name, |name| {
+                segment_kind(name, devices_by_name.get(name).copied(), &mounted)
+            })
+        };
+        let disk_swaps = swaps
+            .iter()
+            .filter(|(root, _)| root == &root_dev)
+            .map(|(_, area)| area.clone())
+            .collect();
+        let unmounted = unmounted
+            .iter()
+            .filter(|(root, _)| root == &root_dev)
+            .map(|(_, filesystem)| filesystem.clone())
+            .collect();
+        let mut disk = DiskInfo {
+            device: root_dev,
             model,
-            total_space,
+            total_space: 0,
             partitions,
             is_cdrom,
             icon_name,
-        }));
+            layers,
+            unmounted,
+            swaps: disk_swaps,
+            capacity,
+            layout,
+            backing_file,
+        };
+        disk.total_space = disk.filesystems().iter().map(|p| p.total_space).sum();
+        drives.push(Drive::Local(disk));
     }
     if verbose {
         println!("Total local disks found: {}", drives.len());
     }
 
-    // Network drives via /proc/mounts
-    if let Ok(mounts) = fs::read_to_string("/proc/mounts") {
-        for line in mounts.lines() {
-            let fields: Vec<&str> = line.split_whitespace().collect();
-            if fields.len() >= 3 {
-                let fstype = fields[2];
-                if fstype.starts_with("nfs") || matches!(fstype, "cifs" | "smbfs") {
-                    let mount_point = fields[1].to_string();
-                    match statvfs(mount_point.as_str()) {
-                        Ok(stat) => {
-                            let block_size = stat.block_size() as u64;
-                            let total = stat.blocks() * block_size;
-                            let free = stat.blocks_free() * block_size;
-                            let used = total - free;
+    // Network drives via mountinfo
+    let network_filesystems = NetworkFilesystems::load(verbose);
 
-                            if verbose {
-                                println!(
-                                    "Network drive detected: {} (type: {}, total: {}, used: {})",
-                                    mount_point, fstype, total, used
-                                );
-                            }
+    // Stat every network mount at once so several dead servers only cost one timeout
+    let mut network_mounts: Vec<(&MountInfo, Vec<String>)> = Vec::new();
+    for mount in &mount_table {
+        if network_filesystems.icon_for(&mount.fs_type).is_none() {
+            continue;
+        }
+        // One entry per export, further mounts of it are listed on that entry
+        match network_mounts
+            .iter_mut()
+            .find(|(first, _)| first.source == mount.source && first.fs_type == mount.fs_type)
+        {
+            Some((_, others)) => others.push(mount.mount_point.clone()),
+            None => network_mounts.push((mount, Vec::new())),
+        }
+    }
 
-                            drives.push(Drive::Network(NetworkDrive {
-                                mount_point,
-                                used_space: used,
-                                total_space: total,
-                            }));
-                        }
-                        Err(e) => {
-                            if verbose {
-                                println!("Failed to statvfs network drive {}: {:?}", mount_point, e);
-                            }
-                        }
-                    }
+    let mut pending = Vec::new();
+    for (mount, other_mount_points) in network_mounts {
+        let icon_name = network_filesystems.icon_for(&mount.fs_type).unwrap_or_default().to_string();
+        let stat_path = mount.mount_point.clone();
+        // Still None when the last scan's stat of this mount never returned
+        let stat = Pending::spawn_once(format!("statvfs {}", stat_path), move || statvfs(stat_path.as_str()));
+        pending.push((mount.mount_point.clone(), other_mount_points, mount.source.clone(), mount.fs_type.clone(), icon_name, stat));
+    }
+
+    let deadline = Instant::now() + IO_TIMEOUT;
+    for (mount_point, other_mount_points, source, fstype, icon_name, stat) in pending {
+        match stat.and_then(|stat| stat.wait_until(deadline)) {
+            Some(Ok(stat)) => {
+                let block_size = stat.block_size() as u64;
+                let total = stat.blocks() * block_size;
+                let free = stat.blocks_free() * block_size;
+                let used = total - free;
+
+                if verbose {
+                    println!(
+                        "Network drive detected: {} (type: {}, total: {}, used: {})",
+                        mount_point, fstype, total, used
+                    );
+                }
+
+                drives.push(Drive::Network(NetworkDrive {
+                    mount_point,
+                    source,
+                    file_system: fstype,
+                    icon_name,
+                    used_space: used,
+                    total_space: total,
+                    other_mount_points,
+                    responding: true,
+                }));
+            }
+            Some(Err(e)) => {
+                if verbose {
+                    println!("Failed to statvfs network drive {}: {:?}", mount_point, e);
+                }
+            }
+            None => {
+                if verbose {
+                    println!("Network drive {} is not responding", mount_point);
                 }
+
+                drives.push(Drive::Network(NetworkDrive {
+                    mount_point,
+                    source,
+                    file_system: fstype,
+                    icon_name,
+                    used_space: 0,
+                    total_space: 0,
+                    other_mount_points,
+                    responding: false,
+                }));
             }
         }
     }
@@ -279,4 +517,455 @@ pub async fn scan_disks() -> Vec<Drive> {
         println!("Total drives returned (local + network): {}", drives.len());
     }
     drives
-}
\ No newline at end of file
+}
+
+// Whole disk a block device belongs to. Partitions are grouped under their physical disk using
+// udev's parent device, falling back to the sysfs layout. Disks (md, dm, loop...) stand on their own.
+fn root_disk(device: &Device, dev_path: &str) -> String {
+    let sysname = device.sysname().to_string_lossy().to_string();
+    if sysfs::is_partition(Path::new(SYSFS_ROOT), &sysname) {
+        device
+            .parent()
+            .and_then(|parent| parent.devnode().map(|node| node.to_string_lossy().to_string()))
+            .or_else(|| sysfs::parent_disk(Path::new(SYSFS_ROOT), &sysname).map(|disk| format!("/dev/{}", disk)))
+            .unwrap_or_else(|| dev_path.to_string())
+    } else {
+        dev_path.to_string()
+    }
+}
+
+// Everywhere a block device is mounted. Matched by device number, or by source for filesystems
+// like btrfs that report an anonymous one. The filesystem root comes first, then bind and
+// subvolume mounts in mount order.
+fn mounts_of<'a>(mounted: &'a [MountInfo], devnum: Option<dev_t>, dev_path: &str) -> Vec<&'a MountInfo> {
+    let mut mounts: Vec<&MountInfo> = mounted
+        .iter()
+        .filter(|mount| {
+            devnum.is_some_and(|devnum| mount.major == major(devnum) && mount.minor == minor(devnum))
+                || mount.source == dev_path
+        })
+        .collect();
+    mounts.sort_by_key(|mount| mount.is_subtree());
+    mounts
+}
+
+// statvfs can hang forever on a stuck FUSE daemon or dead server, so it gets a deadline
+fn statvfs_with_timeout(mount_point: &str) -> Option<nix::Result<Statvfs>> {
+    let mount_point = mount_point.to_string();
+    run_with_timeout(format!("statvfs {}", mount_point), move || statvfs(mount_point.as_str()))
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SpaceUsage {
+    total: u64,
+    used: u64,
+    available: u64,
+    reserved: u64,
+    total_inodes: u64,
+    used_inodes: u64,
+}
+
+impl SpaceUsage {
+    // blocks_free counts root's reserve, blocks_available doesn't
+    fn from_stat(stat: &Statvfs) -> Self {
+        let block_size = stat.block_size() as u64;
+        let total = stat.blocks() * block_size;
+        let free = stat.blocks_free() * block_size;
+        let available = (stat.blocks_available() * block_size).min(free);
+        SpaceUsage {
+            total,
+            used: total - free,
+            available,
+            reserved: free - available,
+            total_inodes: stat.files(),
+            used_inodes: stat.files() - stat.files_free().min(stat.files()),
+        }
+    }
+}
+
+// None when the mount doesn't answer in time
+fn filesystem_space(device: &Device, dev_path: &str, mount_point: &str, is_cdrom: bool, verbose: bool) -> Option<SpaceUsage> {
+    if is_cdrom {
+        match statvfs_with_timeout(mount_point) {
+            Some(Ok(stat)) => {
+                // Read-only media is always full
+                let usage = SpaceUsage::from_stat(&stat);
+                Some(SpaceUsage { used: usage.total, available: 0, reserved: 0, ..usage })
+            }
+            _ => {
+                let size_str = device.property_value("SIZE").map(|s| s.to_string_lossy().to_string()).unwrap_or("0".to_string());
+                let size = size_str.parse::<u64>().unwrap_or(0) * 512;
+                if verbose {
+                    println!("CDROM fallback for {}: SIZE={}", dev_path, size);
+                }
+                Some(SpaceUsage { total: size, used: size, ..SpaceUsage::default() })
+            }
+        }
+    } else {
+        match statvfs_with_timeout(mount_point) {
+            Some(Ok(stat)) => Some(SpaceUsage::from_stat(&stat)),
+            Some(Err(e)) => {
+                if verbose {
+                    println!("Failed to statvfs {} ({}): {:?}", dev_path, mount_point, e);
+                }
+                Some(SpaceUsage::default())
+            }
+            None => {
+                if verbose {
+                    println!("{} ({}) is not responding", dev_path, mount_point);
+                }
+                None
+            }
+        }
+    }
+}
+
+// A filesystem udev knows about but nobody has mounted. Devices already in use by a stacked
+// device (LUKS, LVM, md) and optical discs are left out.
+fn unmounted_filesystem(device: &Device, dev_path: &str) -> Option<UnmountedFilesystem> {
+    let property = |name: &str| device.property_value(name).map(|value| value.to_string_lossy().to_string());
+    if property("ID_FS_USAGE").as_deref() != Some("filesystem") || property("ID_CDROM").is_some() {
+        return None;
+    }
+    let sysname = device.sysname().to_string_lossy().to_string();
+    if !sysfs::holders(Path::new(SYSFS_ROOT), &sysname).is_empty() {
+        return None;
+    }
+
+    Some(UnmountedFilesystem {
+        device: dev_path.to_string(),
+        file_system: property("ID_FS_TYPE")?,
+        label: property("ID_FS_LABEL").filter(|label| !label.is_empty()),
+        size: sysfs::size_bytes(Path::new(SYSFS_ROOT), &sysname).unwrap_or(0),
+    })
+}
+
+// Gaps smaller than this are partition alignment or the GPT backup header, not usable space
+const MIN_FREE_SEGMENT: u64 = 16 * 1024 * 1024;
+
+// Partitions and unallocated gaps of a disk, for the layout bar. A disk without a partition
+// table is a single segment.
+fn disk_layout(sysfs_root: &Path, disk_name: &str, kind_of: impl Fn(&str) -> SegmentKind) -> (u64, Vec<LayoutSegment>) {
+    let capacity = sysfs::size_bytes(sysfs_root, disk_name).unwrap_or(0);
+
+    let mut partitions: Vec<LayoutSegment> = sysfs::partitions(sysfs_root, disk_name)
+        .into_iter()
+        .filter_map(|name| {
+            let start = sysfs::start_bytes(sysfs_root, &name)?;
+            let size = sysfs::size_bytes(sysfs_root, &name)?;
+            let kind = kind_of(&name);
+            Some(LayoutSegment { name, kind, start, size })
+        })
+        .collect();
+    if partitions.is_empty() {
+        let kind = kind_of(disk_name);
+        return (capacity, vec![LayoutSegment { name: disk_name.to_string(), kind, start: 0, size: capacity }]);
+    }
+    partitions.sort_by_key(|segment| segment.start);
+
+    let mut layout = Vec::new();
+    let mut offset = 0;
+    for partition in partitions {
+        if partition.start >= offset + MIN_FREE_SEGMENT {
+            layout.push(LayoutSegment { name: String::new(), kind: SegmentKind::Free, start: offset, size: partition.start - offset });
+        }
+        offset = offset.max(partition.start + partition.size);
+        layout.push(partition);
+    }
+    if capacity >= offset + MIN_FREE_SEGMENT {
+        layout.push(LayoutSegment { name: String::new(), kind: SegmentKind::Free, start: offset, size: capacity - offset });
+    }
+    (capacity, layout)
+}
+
+fn segment_kind(name: &str, device: Option<&Device>, mounted: &[MountInfo]) -> SegmentKind {
+    let property = |key: &str| {
+        device
+            .and_then(|device| device.property_value(key))
+            .map(|value| value.to_string_lossy().to_string())
+    };
+    let devnum = device.and_then(|device| device.devnum());
+
+    if !mounts_of(mounted, devnum, &format!("/dev/{}", name)).is_empty() {
+        SegmentKind::Mounted
+    } else if !sysfs::holders(Path::new(SYSFS_ROOT), name).is_empty() {
+        SegmentKind::Stacked
+    } else if property("ID_FS_TYPE").as_deref() == Some("swap") {
+        SegmentKind::Swap
+    } else if property("ID_FS_USAGE").as_deref() == Some("filesystem") {
+        SegmentKind::Unmounted
+    } else {
+        SegmentKind::Other
+    }
+}
+
+// Everything shown about a mounted filesystem; mounts holds at least its primary mount and space
+// is None when it didn't answer statvfs in time
+fn partition_info(device: Option<&Device>, display_device: String, mounts: &[&MountInfo], space: Option<SpaceUsage>) -> PartitionInfo {
+    let property = |name: &str| {
+        device
+            .and_then(|device| device.property_value(name))
+            .map(|value| value.to_string_lossy().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let mount = mounts[0];
+    let mount_options: Vec<String> = mount.options().into_iter().map(str::to_string).collect();
+    let read_only = mount.is_read_only();
+    let responding = space.is_some();
+    let space = space.unwrap_or_default();
+
+    PartitionInfo {
+        device: display_device,
+        mount_point: mount.mount_point.clone(),
+        total_space: space.total,
+        used_space: space.used,
+        available_space: space.available,
+        reserved_space: space.reserved,
+        total_inodes: space.total_inodes,
+        used_inodes: space.used_inodes,
+        file_system: mount.fs_type.clone(),
+        other_mount_points: mounts[1..].iter().map(|mount| mount.mount_point.clone()).collect(),
+        label: property("ID_FS_LABEL"),
+        uuid: property("ID_FS_UUID"),
+        part_label: property("ID_PART_ENTRY_NAME"),
+        mount_options,
+        read_only,
+        responding,
+    }
+}
+
+fn drive_icon(device: &Device, is_cdrom: bool) -> &'static str {
+    if is_cdrom {
+        "drive-optical"
+    } else {
+        let media = device.property_value("ID_DRIVE_MEDIA").map(|s| s.to_string_lossy().to_string());
+        match media.as_deref() {
+            Some("solidstate") => "drive-harddisk-solidstate",
+            Some("disk") => "drive-harddisk",
+            Some("flash") | Some("usb") => "drive-removable-media",
+            Some("floppy") => "drive-floppy",
+            _ => if device.property_value("ID_BUS").map_or(false, |b| b == "usb") {
+                "drive-removable-media"
+            } else {
+                "drive-harddisk"
+            }
+        }
+    }
+}
+
+// Build the stack of devices held by a block device, e.g. partition -> LUKS -> volume group -> volumes
+fn block_layers(
+    name: &str,
+    devices: &HashMap<String, &Device>,
+    mounted: &[MountInfo],
+    swaps: &[SwapArea],
+    verbose: bool,
+) -> Vec<BlockLayer> {
+    let sysfs_root = Path::new(SYSFS_ROOT);
+    let mut layers = Vec::new();
+    let mut volume_groups: Vec<BlockLayer> = Vec::new();
+
+    for holder in sysfs::holders(sysfs_root, name) {
+        let device = devices.get(&holder);
+        let dev_path = format!("/dev/{}", holder);
+        let dm_name = sysfs::dm_name(sysfs_root, &holder);
+        let dm_uuid = sysfs::dm_uuid(sysfs_root, &holder).unwrap_or_default();
+
+        let (kind, display_name) = if let Some(level) = sysfs::md_level(sysfs_root, &holder) {
+            (LayerKind::Raid, format!("{} ({})", holder, level))
+        } else if dm_uuid.starts_with("CRYPT-") {
+            (LayerKind::Luks, dm_name.clone().unwrap_or_else(|| holder.clone()))
+        } else if dm_uuid.starts_with("LVM-") {
+            let lv_name = device
+                .and_then(|device| device.property_value("DM_LV_NAME"))
+                .map(|lv| lv.to_string_lossy().to_string())
+                .or_else(|| dm_name.as_deref().map(|dm_name| split_lvm_name(dm_name).1))
+                .unwrap_or_else(|| holder.clone());
+            (LayerKind::LogicalVolume, lv_name)
+        } else {
+            (LayerKind::DeviceMapper, dm_name.clone().unwrap_or_else(|| holder.clone()))
+        };
+
+        // Arrays and volume groups spanning several devices are expanded under the first one only
+        let shown_elsewhere = sysfs::slaves(sysfs_root, &holder).first().is_some_and(|first| first != name);
+
+        let display_device = dm_name
+            .as_ref()
+            .map(|dm_name| format!("/dev/mapper/{}", dm_name))
+            .unwrap_or_else(|| dev_path.clone());
+        let filesystem = if shown_elsewhere {
+            None
+        } else {
+            let mounts = mounts_of(mounted, device.and_then(|device| device.devnum()), &dev_path);
+            mounts
+                .first()
+                .filter(|mount| !mount.fs_type.is_empty() && mount.fs_type != "unknown")
+                .map(|mount| {
+                    let space = match device {
+                        Some(device) => filesystem_space(device, &dev_path, &mount.mount_point, false, verbose),
+                        None => Some(SpaceUsage::default()),
+                    };
+                    partition_info(device.copied(), display_device.clone(), &mounts, space)
+                })
+        };
+        let unmounted = match device {
+            Some(device) if filesystem.is_none() && !shown_elsewhere => unmounted_filesystem(device, &display_device),
+            _ => None,
+        };
+        // The swap list is resolved to kernel nodes, /dev/mapper/vg-swap is listed as /dev/dm-1
+        let swap = swaps
+            .iter()
+            .find(|area| !shown_elsewhere && area.path == Path::new(&dev_path))
+            .map(|area| SwapArea { path: PathBuf::from(&display_device), ..area.clone() });
+
+        let children = if shown_elsewhere {
+            Vec::new()
+        } else {
+            block_layers(&holder, devices, mounted, swaps, verbose)
+        };
+
+        if verbose {
+            println!("Stacked device {} ({:?}: {}) on {}", dev_path, kind, display_name, name);
+        }
+
+        let layer = BlockLayer {
+            kind,
+            name: display_name,
+            device: dev_path,
+            filesystem,
+            unmounted,
+            swap,
+            children,
+            shown_elsewhere,
+        };
+
+        // Group logical volumes under their volume group
+        if kind == LayerKind::LogicalVolume {
+            let vg_name = device
+                .and_then(|device| device.property_value("DM_VG_NAME"))
+                .map(|vg| vg.to_string_lossy().to_string())
+                .or_else(|| dm_name.as_deref().map(|dm_name| split_lvm_name(dm_name).0))
+                .unwrap_or_default();
+            match volume_groups.iter_mut().find(|group| group.name == vg_name) {
+                Some(group) => group.children.push(layer),
+                None => volume_groups.push(BlockLayer {
+                    kind: LayerKind::VolumeGroup,
+                    name: vg_name,
+                    device: String::new(),
+                    filesystem: None,
+                    unmounted: None,
+                    swap: None,
+                    children: vec![layer],
+                    shown_elsewhere,
+                }),
+            }
+        } else {
+            layers.push(layer);
+        }
+    }
+
+    layers.extend(volume_groups);
+    layers
+}
+
+// Device-mapper names LVM volumes "vg-lv", doubling any dash inside either name
+fn split_lvm_name(dm_name: &str) -> (String, String) {
+    let bytes = dm_name.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'-' {
+            if bytes.get(index + 1) == Some(&b'-') {
+                index += 2;
+                continue;
+            }
+            let vg = dm_name[..index].replace("--", "-");
+            let lv = dm_name[index + 1..].replace("--", "-");
+            return (vg, lv);
+        }
+        index += 1;
+    }
+    (String::new(), dm_name.replace("--", "-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sysfs::fixture::Fixture;
+
+    #[test]
+    fn splits_lvm_names_at_the_single_dash() {
+        assert_eq!(split_lvm_name("vg0-root"), ("vg0".to_string(), "root".to_string()));
+        assert_eq!(split_lvm_name("fedora-home"), ("fedora".to_string(), "home".to_string()));
+    }
+
+    #[test]
+    fn undoubles_dashes_inside_lvm_names() {
+        assert_eq!(split_lvm_name("vg--name-lv--name"), ("vg-name".to_string(), "lv-name".to_string()));
+        assert_eq!(split_lvm_name("my--vg-swap"), ("my-vg".to_string(), "swap".to_string()));
+        assert_eq!(split_lvm_name("vg-lv----x"), ("vg".to_string(), "lv--x".to_string()));
+    }
+
+    #[test]
+    fn name_without_a_separator_is_all_volume() {
+        assert_eq!(split_lvm_name("lonely--lv"), (String::new(), "lonely-lv".to_string()));
+    }
+
+    const MIB: u64 = 1024 * 1024;
+
+    // sda in a throwaway sysfs with partitions given as (start, size) in MiB
+    fn fixture_disk(name: &str, size: u64, partitions: &[(u64, u64)]) -> Fixture {
+        let sysfs = Fixture::new(&format!("layout-{}", name));
+        sysfs.device("pci0000:00/ata1/block/sda").size("sda", size * MIB);
+        for (index, (start, size)) in partitions.iter().enumerate() {
+            let partition = format!("sda{}", index + 1);
+            sysfs
+                .partition(&format!("pci0000:00/ata1/block/sda/{}", partition), index as u32 + 1)
+                .start(&partition, start * MIB)
+                .size(&partition, size * MIB);
+        }
+        sysfs
+    }
+
+    fn summary(layout: &[LayoutSegment]) -> Vec<(&str, SegmentKind, u64, u64)> {
+        layout
+            .iter()
+            .map(|segment| (segment.name.as_str(), segment.kind, segment.start / MIB, segment.size / MIB))
+            .collect()
+    }
+
+    #[test]
+    fn layout_has_free_space_between_and_after_partitions() {
+        let sysfs = fixture_disk("gaps", 4096, &[(1, 512), (1024, 1024)]);
+        let (capacity, layout) = disk_layout(&sysfs.root, "sda", |_| SegmentKind::Mounted);
+
+        assert_eq!(capacity, 4096 * MIB);
+        assert_eq!(summary(&layout), [
+            ("sda1", SegmentKind::Mounted, 1, 512),
+            ("", SegmentKind::Free, 513, 511),
+            ("sda2", SegmentKind::Mounted, 1024, 1024),
+            ("", SegmentKind::Free, 2048, 2048),
+        ]);
+    }
+
+    #[test]
+    fn layout_skips_alignment_gaps() {
+        let sysfs = fixture_disk("aligned", 1040, &[(1, 511), (513, 526)]);
+        let (_, layout) = disk_layout(&sysfs.root, "sda", |name| {
+            if name == "sda1" { SegmentKind::Swap } else { SegmentKind::Stacked }
+        });
+
+        assert_eq!(summary(&layout), [
+            ("sda1", SegmentKind::Swap, 1, 511),
+            ("sda2", SegmentKind::Stacked, 513, 526),
+        ]);
+    }
+
+    #[test]
+    fn disk_without_partitions_is_one_segment() {
+        let sysfs = fixture_disk("whole", 2048, &[]);
+        let (capacity, layout) = disk_layout(&sysfs.root, "sda", |_| SegmentKind::Unmounted);
+
+        assert_eq!(capacity, 2048 * MIB);
+        assert_eq!(summary(&layout), [("sda", SegmentKind::Unmounted, 0, 2048)]);
+    }
+}
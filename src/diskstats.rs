@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::Instant;
+
+// Sectors in diskstats are 512 bytes regardless of the device's block size
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskCounters {
+    pub sectors_read: u64,
+    pub sectors_written: u64,
+    // Milliseconds the device had requests in flight, stops counting while idle
+    pub busy_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiskActivity {
+    pub read_per_sec: u64,
+    pub write_per_sec: u64,
+    // Share of the interval the device was busy, 0.0 to 1.0
+    pub utilization: f32,
+}
+
+impl DiskActivity {
+    pub fn is_busy(&self) -> bool {
+        self.read_per_sec > 0 || self.write_per_sec > 0 || self.utilization > 0.0
+    }
+}
+
+// Counters by kernel name, see Documentation/admin-guide/iostats.rst
+// 8 0 sda 4521 1203 301234 2201 3310 2020 98112 4102 0 3520 6303 ...
+pub fn parse_diskstats(contents: &str) -> HashMap<String, DiskCounters> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 13 {
+                return None;
+            }
+            let counter = |index: usize| fields[index].parse::<u64>().ok();
+            Some((
+                fields[2].to_string(),
+                DiskCounters {
+                    sectors_read: counter(5)?,
+                    sectors_written: counter(9)?,
+                    busy_ms: counter(12)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+pub fn read_diskstats() -> HashMap<String, DiskCounters> {
+    fs::read_to_string("/proc/diskstats")
+        .map(|contents| parse_diskstats(&contents))
+        .unwrap_or_default()
+}
+
+// Throughput between two samples. Counters can wrap or reset when a device is re-added, which
+// saturates to zero instead of reporting a huge spike.
+pub fn activity(before: &DiskCounters, after: &DiskCounters, elapsed_ms: u64) -> DiskActivity {
+    let elapsed_ms = elapsed_ms.max(1);
+    let per_sec = |sectors: u64| sectors * SECTOR_SIZE * 1000 / elapsed_ms;
+    DiskActivity {
+        read_per_sec: per_sec(after.sectors_read.saturating_sub(before.sectors_read)),
+        write_per_sec: per_sec(after.sectors_written.saturating_sub(before.sectors_written)),
+        utilization: (after.busy_ms.saturating_sub(before.busy_ms) as f32 / elapsed_ms as f32).min(1.0),
+    }
+}
+
+// Keeps the previous sample around so each tick can be turned into rates
+#[derive(Debug, Clone, Default)]
+pub struct DiskStatsSampler {
+    last: Option<(Instant, HashMap<String, DiskCounters>)>,
+}
+
+impl DiskStatsSampler {
+    // Activity per kernel name since the previous call, empty on the first one
+    pub fn sample(&mut self) -> HashMap<String, DiskActivity> {
+        let now = Instant::now();
+        let counters = read_diskstats();
+        let rates = match &self.last {
+            Some((then, previous)) => {
+                let elapsed_ms = now.duration_since(*then).as_millis() as u64;
+                counters
+                    .iter()
+                    .filter_map(|(name, after)| {
+                        let before = previous.get(name)?;
+                        Some((name.clone(), activity(before, after, elapsed_ms)))
+                    })
+                    .collect()
+            }
+            None => HashMap::new(),
+        };
+        self.last = Some((now, counters));
+        rates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_sectors_and_busy_time_from_their_columns() {
+        let stats = parse_diskstats(
+            "   8       0 sda 4521 1203 301234 2201 3310 2020 98112 4102 0 3520 6303 0 0 0 0\n\
+             259       1 nvme0n1p1 10 0 80 1 0 0 0 0 0 7 1\n",
+        );
+        let sda = &stats["sda"];
+        assert_eq!(sda.sectors_read, 301234);
+        assert_eq!(sda.sectors_written, 98112);
+        assert_eq!(sda.busy_ms, 3520);
+        assert_eq!(stats["nvme0n1p1"].busy_ms, 7);
+    }
+
+    #[test]
+    fn skips_short_and_garbled_lines() {
+        let stats = parse_diskstats(
+            "\n\
+             8 0 sda 1 2 3\n\
+             8 16 sdb 1 2 many 4 5 6 7 8 9 10 11\n\
+             8 32 sdc 1 2 3 4 5 6 7 8 9 10 11\n",
+        );
+        assert_eq!(stats.len(), 1);
+        assert!(stats.contains_key("sdc"));
+    }
+
+    #[test]
+    fn turns_counter_deltas_into_rates() {
+        let before = DiskCounters { sectors_read: 1000, sectors_written: 0, busy_ms: 100 };
+        let after = DiskCounters { sectors_read: 3000, sectors_written: 4, busy_ms: 350 };
+        let rates = activity(&before, &after, 500);
+        assert_eq!(rates.read_per_sec, 2000 * SECTOR_SIZE * 2);
+        assert_eq!(rates.write_per_sec, 4 * SECTOR_SIZE * 2);
+        assert_eq!(rates.utilization, 0.5);
+        assert!(rates.is_busy());
+    }
+
+    #[test]
+    fn wrapped_counters_read_as_idle() {
+        let before = DiskCounters { sectors_read: u64::MAX - 5, sectors_written: 900, busy_ms: 800 };
+        let after = DiskCounters { sectors_read: 10, sectors_written: 20, busy_ms: 30 };
+        let rates = activity(&before, &after, 1000);
+        assert_eq!(rates, DiskActivity::default());
+        assert!(!rates.is_busy());
+    }
+
+    #[test]
+    fn no_elapsed_time_does_not_divide_by_zero() {
+        let before = DiskCounters::default();
+        let after = DiskCounters { sectors_read: 2, sectors_written: 0, busy_ms: 5 };
+        let rates = activity(&before, &after, 0);
+        assert_eq!(rates.read_per_sec, 2 * SECTOR_SIZE * 1000);
+        // Busy for longer than the interval still caps at fully busy
+        assert_eq!(rates.utilization, 1.0);
+    }
+}
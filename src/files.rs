@@ -1,20 +1,163 @@
 use crate::crawler::FileEntry;
+use crate::filter::{format_date, FileFilter};
+use crate::reclaim::ReclaimableEntry;
+use crate::sizes::format_size;
+use crate::sunburst::{sunburst_canvas, SunburstView};
+use crate::tree::ScanTree;
+use crate::treemap::{treemap_canvas, TreemapView};
 use crate::ui::Message;
 use cosmic::iced_renderer::fallback::Renderer;
 use cosmic::iced_wgpu::Renderer as WgpuRenderer;
 use cosmic::{
     iced_core::{Alignment, Color, Element, Length},
-    widget::{column, container, row, text, mouse_area, icon, Icon},
+    widget::{column, container, row, text, mouse_area, icon, Icon, icon::IconFallback},
 };
-use cosmic::iced_widget::scrollable;
+use cosmic::iced_widget::{button, scrollable, Stack};
 use iced_tiny_skia::Renderer as SkiaRenderer;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
 type CosmicRenderer = Renderer<WgpuRenderer, SkiaRenderer>;
 
+// Rectangle dimensions
+const RECT_WIDTH: f32 = 50.0;
+const RECT_HEIGHT: f32 = 60.0;
+const GAP: f32 = 1.0;
+
+// List view column widths, the name column takes whatever is left
+const BAR_WIDTH: f32 = 80.0;
+const SIZE_WIDTH: f32 = 90.0;
+const SHARE_WIDTH: f32 = 60.0;
+const ITEMS_WIDTH: f32 = 70.0;
+const MODIFIED_WIDTH: f32 = 90.0;
+const ROW_ICON_SIZE: u16 = 16;
+const CELL_SPACING: f32 = 4.0;
+
+// Hover details for a node of the crawl tree. Top-level entries carry the crawler's reclaim and
+// swap tags, anything deeper is described from the tree alone.
+pub fn entry_for_node(tree: &ScanTree, entries: &[(usize, FileEntry)], node: usize) -> FileEntry {
+    if let Some((_, entry)) = entries.iter().find(|(top, _)| *top == node) {
+        return entry.clone();
+    }
+    let info = &tree.nodes[node];
+    FileEntry {
+        path: tree.path(node),
+        size: info.size,
+        is_dir: info.is_dir,
+        modified: info.modified,
+        reclaimable: None,
+        swap: false,
+        icons: Vec::new(),
+    }
+}
+
+// How the folder being browsed is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewMode {
+    #[default]
+    Grid,
+    // Tiles sized by their share of the folder, with subfolders nested inside
+    Treemap,
+    // Rings around the folder, one per level below it
+    Sunburst,
+    // One row per entry with sortable columns
+    List,
+    // Folders that open in place, below their parent
+    Tree,
+}
+
+// Arrow keys in the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusMove {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+// Tile index after moving from `from` in a grid of `len` tiles laid out `per_row` to a row.
+// Moves off the edges stay put, nothing focused yet starts at the first tile.
+pub fn move_focus(from: Option<usize>, direction: FocusMove, len: usize, per_row: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let Some(from) = from else {
+        return Some(0);
+    };
+    let to = match direction {
+        FocusMove::Left => from.saturating_sub(1),
+        FocusMove::Right => from + 1,
+        FocusMove::Up => from.checked_sub(per_row).unwrap_or(from),
+        // The last row may be short, land on its last tile rather than not moving at all
+        FocusMove::Down if from / per_row < (len - 1) / per_row => from + per_row,
+        FocusMove::Down => from,
+    };
+    Some(to.min(len - 1))
+}
+
+// Label and path of each breadcrumb, the mount point first and then every folder below it down
+// to `current_path`. A path outside the mount point has nothing to climb through, it gets a single
+// crumb named after itself.
+pub fn crumbs(mount_point: &str, current_path: &str) -> Vec<(String, PathBuf)> {
+    let current = PathBuf::from(current_path);
+    let Ok(relative) = current.strip_prefix(mount_point) else {
+        return vec![(current_path.to_string(), current)];
+    };
+    let mut path = PathBuf::from(mount_point);
+    let mut crumbs = vec![(mount_point.to_string(), path.clone())];
+    for component in relative.components() {
+        path.push(component);
+        crumbs.push((component.as_os_str().to_string_lossy().to_string(), path.clone()));
+    }
+    crumbs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    Name,
+    Size,
+    Share,
+    Items,
+    Modified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListSort {
+    pub column: SortColumn,
+    pub descending: bool,
+}
+
+impl Default for ListSort {
+    fn default() -> Self {
+        ListSort { column: SortColumn::Size, descending: true }
+    }
+}
+
+impl ListSort {
+    // Clicking the sorted column again flips it, a new column starts with names A to Z and
+    // everything else largest or newest first
+    pub fn toggle(self, column: SortColumn) -> ListSort {
+        if self.column == column {
+            ListSort { column, descending: !self.descending }
+        } else {
+            ListSort { column, descending: column != SortColumn::Name }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Files {
     pub mount_point: String,
     pub current_path: String,
     pub entries: Vec<FileEntry>,
+    pub reclaimable: Vec<ReclaimableEntry>,
+    pub tree: Arc<ScanTree>,
+    // Tile to mark after jumping here from a search result
+    pub highlighted: Option<PathBuf>,
+    // Tile picked with the keyboard
+    pub focused: Option<PathBuf>,
+    pub not_responding: bool,
     pub verbose: bool,
 }
 
@@ -25,19 +168,31 @@ impl Files {
             .build()
             .expect("Failed to create Tokio runtime");
         rt.block_on(async {
-            let entries = crate::crawler::crawl_files(current_path.clone(), verbose).await;
+            let result = crate::crawler::crawl_files(current_path.clone(), verbose).await;
             if verbose {
-                eprintln!("Files loaded entries for {}: {:?}", current_path, entries);
+                eprintln!("Files loaded entries for {}: {:?}", current_path, result.entries);
             }
             Files {
                 current_path,
                 mount_point,
-                entries,
+                entries: result.entries,
+                reclaimable: result.reclaimable,
+                tree: result.tree,
+                highlighted: None,
+                focused: None,
+                not_responding: result.not_responding,
                 verbose,
             }
         })
     }
 
+    // Map size (in bytes) onto the 0 to 1 TB color scale
+    pub fn color_for_size(size: u64) -> Color {
+        let size_in_tb = size.max(1) as f32 / 1_099_511_627_776.0_f32;
+        let color_value = (size_in_tb * 1024.0_f32).min(1024.0_f32);
+        Self::calculate_color(color_value)
+    }
+
     // Calculate the color for a file based on its size
     fn calculate_color(color_value: f32) -> Color {
         if color_value <= 205.0_f32 {
@@ -88,20 +243,79 @@ impl Files {
         }
     }
 
-    pub fn view<'a>(&self, _available_height: f32, available_width: f32) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
-        // Sort entries by size in descending order
-        let mut sorted_entries = self.entries.clone();
+    // Split entries into (visible, hidden) counts and total sizes for the given filter
+    pub fn filter_stats(&self, filter: &FileFilter) -> ((usize, u64), (usize, u64)) {
+        self.entries.iter().fold(((0, 0), (0, 0)), |(visible, hidden), entry| {
+            if filter.matches(entry) {
+                ((visible.0 + 1, visible.1 + entry.size), hidden)
+            } else {
+                (visible, (hidden.0 + 1, hidden.1 + entry.size))
+            }
+        })
+    }
+
+    // Sort entries by size in descending order, leaving out anything the filter hides
+    fn visible_entries(&self, filter: &FileFilter) -> Vec<FileEntry> {
+        let mut sorted_entries: Vec<FileEntry> = self.entries.iter()
+            .filter(|entry| filter.matches(entry))
+            .cloned()
+            .collect();
         sorted_entries.sort_by(|a, b| b.size.cmp(&a.size));
+        sorted_entries
+    }
 
-        // Rectangle dimensions
-        let rect_width = 50.0_f32;
-        let rect_height = 60.0_f32;
-        let gap = 1.0_f32;
-        let total_rect_width = rect_width + gap;
+    // Calculate the number of rectangles that fit per row based on the available width
+    fn rects_per_row(available_width: f32) -> usize {
+        let rects_per_row = ((available_width / (RECT_WIDTH + GAP)).round() as usize).saturating_sub(6); // Subtract 6 or it draws off-screen
+        rects_per_row.max(1)
+    }
+
+    pub fn grid_id() -> cosmic::widget::Id {
+        cosmic::widget::Id::new("files-grid")
+    }
 
-        // Calculate the number of rectangles that fit per row based on the available width
-        let rects_per_row = ((available_width / total_rect_width).round() as usize).saturating_sub(6); // Subtract 6 or it draws off-screen
-        let rects_per_row = rects_per_row.max(1);
+    // Vertical scroll offset that brings the row holding this path into view
+    pub fn scroll_offset_for(&self, filter: &FileFilter, path: &std::path::Path, available_width: f32) -> Option<f32> {
+        let index = self.visible_entries(filter).iter().position(|entry| entry.path == path)?;
+        let row = index / Self::rects_per_row(available_width);
+        Some(row as f32 * (RECT_HEIGHT + GAP))
+    }
+
+    // Move the keyboard focus through the grid and return the newly focused entry. With nothing
+    // focused yet, e.g. after going back, the marked tile already looks like the cursor, so the
+    // first press moves on from it rather than just picking it up.
+    pub fn move_focus(&mut self, filter: &FileFilter, direction: FocusMove, available_width: f32) -> Option<FileEntry> {
+        let entries = self.visible_entries(filter);
+        let from = self.focused.as_ref()
+            .or(self.highlighted.as_ref())
+            .and_then(|path| entries.iter().position(|entry| &entry.path == path));
+        let to = move_focus(from, direction, entries.len(), Self::rects_per_row(available_width));
+        let entry = to.map(|index| entries[index].clone());
+        self.focused = entry.as_ref().map(|entry| entry.path.clone());
+        entry
+    }
+
+    // Scroll offset that brings the focused tile fully into view, None when it already is
+    pub fn scroll_to_focus(&self, filter: &FileFilter, available_width: f32, available_height: f32, scroll: f32) -> Option<f32> {
+        let top = self.scroll_offset_for(filter, self.focused.as_ref()?, available_width)?;
+        let bottom = top + RECT_HEIGHT;
+        if top < scroll {
+            Some(top)
+        } else if bottom > scroll + available_height {
+            Some(bottom - available_height)
+        } else {
+            None
+        }
+    }
+
+    pub fn view<'a>(&self, filter: &FileFilter, _available_height: f32, available_width: f32) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        let sorted_entries = self.visible_entries(filter);
+
+        let rect_width = RECT_WIDTH;
+        let rect_height = RECT_HEIGHT;
+        let gap = GAP;
+        let total_rect_width = rect_width + gap;
+        let rects_per_row = Self::rects_per_row(available_width);
 
         // Calculate the number of rows needed
         let _total_rects = sorted_entries.len();
@@ -123,30 +337,35 @@ impl Files {
         let mut rect_count = 0;
 
         for entry in sorted_entries.iter() {
-            let size = entry.size.max(1) as f32;
+            let color = Self::color_for_size(entry.size);
 
-            // Map size (in bytes) to 0-1024GB scale (0 to 1 TB)
-            let size_in_tb = size / 1_099_511_627_776.0_f32;
-            let color_value = (size_in_tb * 1024.0_f32).min(1024.0_f32);
-            let color = Self::calculate_color(color_value);
-
-            // Select the icon based on whether the entry is a directory or file
-            let icon_name = if entry.is_dir { "folder" } else { "text-x-generic" };
-            let icon_widget: Icon = icon::from_name(icon_name)
+            // Icon from the file's MIME type or the folder's XDG role, plain folder or file otherwise
+            let icon_widget: Icon = Self::entry_icon(entry)
                 .size(48) // Set icon size to 48x48 pixels
                 .into();  // Convert Named to Icon
 
+            // Outline the tile we jumped to from a search
+            let highlighted = self.highlighted.as_ref() == Some(&entry.path);
+            let focused = self.focused.as_ref() == Some(&entry.path);
+
             // Create the rectangle with the icon centered
             let rect = container(icon_widget)
                 .width(Length::Fixed(rect_width))
                 .height(Length::Fixed(rect_height))
                 .align_x(Alignment::Center)
                 .align_y(Alignment::Center)
-                .style(move |_| container::Style {
+                .style(move |theme: &cosmic::Theme| container::Style {
                     background: Some(color.into()),
                     border: cosmic::iced::Border {
-                        color: Color::TRANSPARENT,
-                        width: 0.0,
+                        // The keyboard focus ring wins over the search mark
+                        color: if focused {
+                            Color::from(theme.cosmic().accent_color())
+                        } else if highlighted {
+                            Color::WHITE
+                        } else {
+                            Color::TRANSPARENT
+                        },
+                        width: if focused || highlighted { 3.0 } else { 0.0 },
                         radius: 12.0.into(),
                     },
                     text_color: None,
@@ -154,9 +373,36 @@ impl Files {
                     shadow: cosmic::iced_core::Shadow::default(),
                 });
 
+            // Badge regenerable locations with a small trash icon in the corner, active swap files with a label
+            let badge: Option<Element<'a, Message, cosmic::Theme, CosmicRenderer>> = if entry.reclaimable.is_some() {
+                let trash: Icon = icon::from_name("user-trash-full-symbolic")
+                    .size(16)
+                    .into();
+                Some(trash.into())
+            } else if entry.swap {
+                Some(text("swap").size(10.0).into())
+            } else {
+                None
+            };
+            let tile: Element<'a, Message, cosmic::Theme, CosmicRenderer> = if let Some(badge) = badge {
+                let layers: Vec<Element<'a, Message, cosmic::Theme, CosmicRenderer>> = vec![
+                    rect.into(),
+                    container(badge)
+                        .width(Length::Fixed(rect_width))
+                        .height(Length::Fixed(rect_height))
+                        .align_x(Alignment::End)
+                        .align_y(Alignment::End)
+                        .padding(2)
+                        .into(),
+                ];
+                Stack::with_children(layers).into()
+            } else {
+                rect.into()
+            };
+
             let subfolder = entry.path.to_string_lossy().to_string();
             let is_dir = entry.is_dir;
-            let rect_with_interaction = mouse_area(rect)
+            let rect_with_interaction = mouse_area(tile)
                 .on_enter(Message::HoverUpdate(Some(entry.clone())))
                 .on_exit(Message::HoverUpdate(None))
                 .on_press(if is_dir {
@@ -205,8 +451,430 @@ impl Files {
             column_widget = column_widget.push(row);
         }
         scrollable(column_widget)
+            .id(Self::grid_id())
+            .on_scroll(|viewport| Message::GridScrolled(viewport.absolute_offset().y))
+            .width(Length::Fixed(available_width))
+            .height(Length::Fill)
+            .into()
+    }
+
+    pub fn treemap_view<'a>(&self, filter: &FileFilter) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        let entries = self.visible_entries(filter)
+            .into_iter()
+            .filter_map(|entry| Some((self.tree.find(&entry.path)?, entry)))
+            .collect();
+        let highlighted = self.highlighted.as_ref().and_then(|path| self.tree.find(path));
+        treemap_canvas(TreemapView {
+            tree: self.tree.clone(),
+            entries,
+            highlighted,
+        })
+    }
+
+    pub fn sunburst_view<'a>(&self, filter: &FileFilter) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        let entries = self.visible_entries(filter)
+            .into_iter()
+            .filter_map(|entry| Some((self.tree.find(&entry.path)?, entry)))
+            .collect();
+        let highlighted = self.highlighted.as_ref().and_then(|path| self.tree.find(path));
+        sunburst_canvas(SunburstView {
+            tree: self.tree.clone(),
+            entries,
+            highlighted,
+        })
+    }
+
+    // Share of the parent folder as a filled strip
+    fn share_bar<'a>(share: f32, color: Color) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        container(
+            container(text(""))
+                .width(Length::Fixed((BAR_WIDTH - 2.0) * share))
+                .height(Length::Fill)
+                .style(move |_| container::Style {
+                    background: Some(color.into()),
+                    ..container::Style::default()
+                })
+        )
+            .width(Length::Fixed(BAR_WIDTH))
+            .height(Length::Fixed(10.0))
+            .padding(1)
+            .style(|_| container::Style {
+                border: cosmic::iced::Border {
+                    color: Color::from_rgba(0.5, 0.5, 0.5, 0.6),
+                    width: 1.0,
+                    radius: 2.0.into(),
+                },
+                ..container::Style::default()
+            })
+            .into()
+    }
+
+    // Themed icon for an entry, falling back through less specific names to the plain default
+    fn entry_icon(entry: &FileEntry) -> icon::Named {
+        let default = Self::icon_name(entry);
+        let Some((first, rest)) = entry.icons.split_first() else {
+            return icon::from_name(default);
+        };
+        let mut fallbacks: Vec<Cow<'static, str>> = rest.iter().cloned().map(Cow::Owned).collect();
+        fallbacks.push(Cow::Borrowed(default));
+        icon::from_name(first.clone()).fallback(Some(IconFallback::Names(fallbacks)))
+    }
+
+    fn icon_name(entry: &FileEntry) -> &'static str {
+        if entry.is_dir {
+            "folder"
+        } else if entry.swap {
+            "media-memory"
+        } else {
+            "text-x-generic"
+        }
+    }
+
+    fn sort_header<'a>(label: &'static str, column: SortColumn, sort: ListSort, width: Length) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        let label = if sort.column == column {
+            format!("{} {}", label, if sort.descending { "▼" } else { "▲" })
+        } else {
+            label.to_string()
+        };
+        button(text(label).size(12.0))
+            .width(width)
+            .padding([2, 4])
+            .on_press(Message::SortList(column))
+            .into()
+    }
+
+    pub fn list_view<'a>(&self, filter: &FileFilter, sort: ListSort, available_width: f32) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        // Shares are of the whole folder, so hiding entries doesn't inflate the ones left
+        let folder_size = self.entries.iter().map(|entry| entry.size).sum::<u64>().max(1);
+        let mut rows: Vec<(FileEntry, Option<usize>)> = self.visible_entries(filter)
+            .into_iter()
+            .map(|entry| {
+                let items = if entry.is_dir {
+                    self.tree.find(&entry.path).map(|node| self.tree.nodes[node].items)
+                } else {
+                    None
+                };
+                (entry, items)
+            })
+            .collect();
+
+        rows.sort_by(|(a, a_items), (b, b_items)| {
+            let ordering = match sort.column {
+                SortColumn::Name => {
+                    let name = |entry: &FileEntry| entry.path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+                    name(a).cmp(&name(b))
+                }
+                SortColumn::Size | SortColumn::Share => a.size.cmp(&b.size),
+                SortColumn::Items => a_items.cmp(b_items),
+                SortColumn::Modified => a.modified.cmp(&b.modified),
+            };
+            if sort.descending { ordering.reverse() } else { ordering }
+        });
+
+        // Laid out like the rows below: room for their icon, and Size spans the bar and size cells
+        let header = row()
+            .push(container(text("")).width(Length::Fixed(ROW_ICON_SIZE as f32)))
+            .push(Self::sort_header("Name", SortColumn::Name, sort, Length::Fill))
+            .push(Self::sort_header("Size", SortColumn::Size, sort, Length::Fixed(BAR_WIDTH + CELL_SPACING + SIZE_WIDTH)))
+            .push(Self::sort_header("%", SortColumn::Share, sort, Length::Fixed(SHARE_WIDTH)))
+            .push(Self::sort_header("Items", SortColumn::Items, sort, Length::Fixed(ITEMS_WIDTH)))
+            .push(Self::sort_header("Modified", SortColumn::Modified, sort, Length::Fixed(MODIFIED_WIDTH)))
+            .spacing(CELL_SPACING)
+            .padding([0, 4]);
+
+        let mut list = column().spacing(1);
+        for (entry, items) in rows {
+            let share = entry.size as f32 / folder_size as f32;
+            let color = Self::color_for_size(entry.size);
+            let highlighted = self.highlighted.as_ref() == Some(&entry.path);
+            let line = row()
+                .push(Self::entry_icon(&entry).size(ROW_ICON_SIZE))
+                .push(text(entry.path.file_name().unwrap_or_default().to_string_lossy().to_string()).size(13.0).width(Length::Fill))
+                .push(Self::share_bar(share, color))
+                .push(text(format_size(entry.size)).size(13.0).width(Length::Fixed(SIZE_WIDTH)))
+                .push(text(format!("{:.1}%", share * 100.0)).size(13.0).width(Length::Fixed(SHARE_WIDTH)))
+                .push(text(items.map(|count| count.to_string()).unwrap_or_default()).size(13.0).width(Length::Fixed(ITEMS_WIDTH)))
+                .push(text(entry.modified.map(format_date).unwrap_or_default()).size(13.0).width(Length::Fixed(MODIFIED_WIDTH)))
+                .spacing(CELL_SPACING)
+                .padding([2, 4])
+                .align_y(Alignment::Center);
+            let line = container(line)
+                .width(Length::Fill)
+                .style(move |_| container::Style {
+                    border: cosmic::iced::Border {
+                        color: if highlighted { Color::WHITE } else { Color::TRANSPARENT },
+                        width: if highlighted { 2.0 } else { 0.0 },
+                        radius: 4.0.into(),
+                    },
+                    ..container::Style::default()
+                });
+
+            let mut interaction = mouse_area(line)
+                .on_enter(Message::HoverUpdate(Some(entry.clone())))
+                .on_exit(Message::HoverUpdate(None))
+                .on_press(Message::Click);
+            if entry.is_dir {
+                interaction = interaction.on_double_click(Message::CrawlSubfolder(entry.path.to_string_lossy().to_string()));
+            }
+            list = list.push(interaction);
+        }
+
+        column()
+            .push(header)
+            .push(scrollable(list).height(Length::Fill))
+            .spacing(4)
+            .width(Length::Fixed(available_width))
+            .height(Length::Fill)
+            .into()
+    }
+
+    // Rows for these nodes and, below every expanded folder, its contents. Shares are of the parent.
+    fn tree_rows<'a>(
+        &self,
+        top: &[(usize, FileEntry)],
+        nodes: &[usize],
+        parent_size: u64,
+        depth: usize,
+        expanded: &HashSet<PathBuf>,
+        list: &mut Vec<Element<'a, Message, cosmic::Theme, CosmicRenderer>>,
+    ) {
+        let mut nodes = nodes.to_vec();
+        nodes.sort_by_key(|&node| std::cmp::Reverse(self.tree.nodes[node].size));
+
+        for node in nodes {
+            let entry = entry_for_node(&self.tree, top, node);
+            let has_children = !self.tree.nodes[node].children.is_empty();
+            let is_expanded = has_children && expanded.contains(&entry.path);
+            let share = entry.size as f32 / parent_size.max(1) as f32;
+            let highlighted = self.highlighted.as_ref() == Some(&entry.path);
+
+            let expander: Element<'a, Message, cosmic::Theme, CosmicRenderer> = if has_children {
+                let chevron = if is_expanded { "go-down-symbolic" } else { "go-next-symbolic" };
+                icon::from_name(chevron).size(12).into()
+            } else {
+                container(text("")).width(Length::Fixed(12.0)).into()
+            };
+
+            let line = row()
+                .push(container(text("")).width(Length::Fixed(depth as f32 * 16.0)))
+                .push(expander)
+                .push(Self::entry_icon(&entry).size(16))
+                .push(text(entry.path.file_name().unwrap_or_default().to_string_lossy().to_string()).size(13.0).width(Length::Fill))
+                .push(Self::share_bar(share, Self::color_for_size(entry.size)))
+                .push(text(format_size(entry.size)).size(13.0).width(Length::Fixed(SIZE_WIDTH)))
+                .push(text(format!("{:.1}%", share * 100.0)).size(13.0).width(Length::Fixed(SHARE_WIDTH)))
+                .spacing(4)
+                .padding([2, 4])
+                .align_y(Alignment::Center);
+            let line = container(line)
+                .width(Length::Fill)
+                .style(move |_| container::Style {
+                    border: cosmic::iced::Border {
+                        color: if highlighted { Color::WHITE } else { Color::TRANSPARENT },
+                        width: if highlighted { 2.0 } else { 0.0 },
+                        radius: 4.0.into(),
+                    },
+                    ..container::Style::default()
+                });
+
+            let interaction = mouse_area(line)
+                .on_enter(Message::HoverUpdate(Some(entry.clone())))
+                .on_exit(Message::HoverUpdate(None));
+            let interaction = if has_children {
+                interaction.on_press(Message::ToggleTreeNode(entry.path.clone()))
+            } else {
+                interaction.on_press(Message::Click)
+            };
+            list.push(interaction.into());
+
+            if is_expanded {
+                let node_info = &self.tree.nodes[node];
+                self.tree_rows(top, &node_info.children, node_info.size, depth + 1, expanded, list);
+            }
+        }
+    }
+
+    // Baobab-style outline of the folder. Opening a folder only reads the crawl tree, nothing is re-crawled.
+    pub fn tree_view<'a>(&self, filter: &FileFilter, expanded: &HashSet<PathBuf>, available_width: f32) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        let top: Vec<(usize, FileEntry)> = self.visible_entries(filter)
+            .into_iter()
+            .filter_map(|entry| Some((self.tree.find(&entry.path)?, entry)))
+            .collect();
+        let nodes: Vec<usize> = top.iter().map(|(node, _)| *node).collect();
+        let folder_size = self.entries.iter().map(|entry| entry.size).sum::<u64>();
+
+        let mut rows = Vec::new();
+        self.tree_rows(&top, &nodes, folder_size, 0, expanded, &mut rows);
+        let mut list = column().spacing(1);
+        for row in rows {
+            list = list.push(row);
+        }
+
+        scrollable(list)
             .width(Length::Fixed(available_width))
             .height(Length::Fill)
             .into()
     }
-}
\ No newline at end of file
+
+    // Mount point and every folder below it down to this one, each a button that jumps there.
+    // Sizes come from the partition's tree, or from the crawls of folders opened on the way here.
+    // A folder reached straight from a search or a file reveal has no crawl above it, its
+    // ancestors show no size until one of them is opened.
+    pub fn breadcrumbs<'a>(&self, partition_tree: Option<&ScanTree>, folder_sizes: &HashMap<PathBuf, u64>) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        let crumbs = crumbs(&self.mount_point, &self.current_path);
+        let mut bar = row().spacing(2).align_y(Alignment::Center);
+        let last = crumbs.len() - 1;
+        for (index, (label, path)) in crumbs.into_iter().enumerate() {
+            if index > 0 {
+                bar = bar.push(text("›").size(16.0));
+            }
+            let size = if index == last {
+                Some(self.tree.nodes[0].size)
+            } else {
+                partition_tree.and_then(|tree| Some(tree.nodes[tree.find(&path)?].size))
+                    .or_else(|| folder_sizes.get(&path).copied())
+            };
+
+            let crumb = button(text(label).size(14.0)).padding([4, 6]);
+            // The folder being shown has nowhere to go, which also marks it as the current one
+            let crumb = if index == last {
+                crumb
+            } else {
+                crumb.on_press(Message::CrawlSubfolder(path.to_string_lossy().to_string()))
+            };
+            let crumb = match size {
+                Some(size) => mouse_area(crumb)
+                    .on_enter(Message::HoverUpdate(Some(FileEntry {
+                        path,
+                        size,
+                        is_dir: true,
+                        modified: None,
+                        reclaimable: None,
+                        swap: false,
+                        icons: Vec::new(),
+                    })))
+                    .on_exit(Message::HoverUpdate(None))
+                    .into(),
+                None => crumb.into(),
+            };
+            bar = bar.push(crumb);
+        }
+        bar.into()
+    }
+
+    // List of known regenerable locations found on this partition, largest first
+    pub fn reclaim_view<'a>(report: &[ReclaimableEntry], available_width: f32) -> Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+        let total: u64 = report.iter().map(|entry| entry.size).sum();
+
+        let mut list = column()
+            .spacing(4)
+            .push(text(format!("Potentially reclaimable: {}", format_size(total))).size(16.0))
+            .push(text("Informational only, Cosmic Dust never deletes anything. Clean these up with the owning tool or your file browser.").size(12.0));
+
+        for entry in report {
+            let path = entry.path.to_string_lossy().to_string();
+            list = list.push(
+                button(
+                    row()
+                        .push(icon::from_name("folder").size(24))
+                        .push(
+                            column()
+                                .spacing(2)
+                                .push(text(path.clone()))
+                                .push(text(entry.description.clone()).size(12.0))
+                                .width(Length::Fill)
+                        )
+                        .push(text(format_size(entry.size)))
+                        .spacing(8)
+                        .align_y(Alignment::Center)
+                )
+                    .width(Length::Fill)
+                    .padding([4, 8])
+                    .on_press(Message::CrawlSubfolder(path))
+            );
+        }
+
+        scrollable(list)
+            .width(Length::Fixed(available_width))
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 7 tiles, 3 to a row:
+    // 0 1 2
+    // 3 4 5
+    // 6
+    #[test]
+    fn moves_through_the_grid_and_stops_at_its_edges() {
+        assert_eq!(move_focus(Some(4), FocusMove::Left, 7, 3), Some(3));
+        assert_eq!(move_focus(Some(4), FocusMove::Right, 7, 3), Some(5));
+        assert_eq!(move_focus(Some(4), FocusMove::Up, 7, 3), Some(1));
+        assert_eq!(move_focus(Some(1), FocusMove::Down, 7, 3), Some(4));
+        assert_eq!(move_focus(Some(0), FocusMove::Left, 7, 3), Some(0));
+        assert_eq!(move_focus(Some(2), FocusMove::Up, 7, 3), Some(2));
+        assert_eq!(move_focus(Some(6), FocusMove::Right, 7, 3), Some(6));
+        assert_eq!(move_focus(Some(6), FocusMove::Down, 7, 3), Some(6));
+    }
+
+    #[test]
+    fn left_and_right_wrap_between_rows() {
+        assert_eq!(move_focus(Some(3), FocusMove::Left, 7, 3), Some(2));
+        assert_eq!(move_focus(Some(2), FocusMove::Right, 7, 3), Some(3));
+    }
+
+    #[test]
+    fn down_into_a_short_last_row_lands_on_its_last_tile() {
+        assert_eq!(move_focus(Some(5), FocusMove::Down, 7, 3), Some(6));
+        assert_eq!(move_focus(Some(3), FocusMove::Down, 7, 3), Some(6));
+    }
+
+    #[test]
+    fn nothing_focused_starts_at_the_first_tile() {
+        for direction in [FocusMove::Left, FocusMove::Right, FocusMove::Up, FocusMove::Down] {
+            assert_eq!(move_focus(None, direction, 7, 3), Some(0));
+        }
+    }
+
+    #[test]
+    fn empty_folder_has_nothing_to_focus() {
+        assert_eq!(move_focus(None, FocusMove::Right, 0, 3), None);
+        assert_eq!(move_focus(Some(2), FocusMove::Down, 0, 3), None);
+    }
+
+    fn labels(crumbs: &[(String, PathBuf)]) -> Vec<&str> {
+        crumbs.iter().map(|(label, _)| label.as_str()).collect()
+    }
+
+    #[test]
+    fn splits_the_path_below_the_mount_point() {
+        let crumbs = crumbs("/home", "/home/user/Music");
+        assert_eq!(labels(&crumbs), ["/home", "user", "Music"]);
+        assert_eq!(crumbs[1].1, PathBuf::from("/home/user"));
+        assert_eq!(crumbs[2].1, PathBuf::from("/home/user/Music"));
+    }
+
+    #[test]
+    fn mount_point_itself_is_a_single_crumb() {
+        assert_eq!(crumbs("/", "/"), [("/".to_string(), PathBuf::from("/"))]);
+        assert_eq!(labels(&crumbs("/home", "/home/")), ["/home"]);
+    }
+
+    #[test]
+    fn root_mount_does_not_double_the_slash() {
+        let crumbs = crumbs("/", "/var/log");
+        assert_eq!(labels(&crumbs), ["/", "var", "log"]);
+        assert_eq!(crumbs[2].1, PathBuf::from("/var/log"));
+    }
+
+    #[test]
+    fn path_outside_the_mount_point_names_itself() {
+        // Only whole components count, /home2 isn't below /home
+        assert_eq!(crumbs("/home", "/home2/user"), [("/home2/user".to_string(), PathBuf::from("/home2/user"))]);
+        assert_eq!(labels(&crumbs("/mnt/usb", "/tmp")), ["/tmp"]);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::crawler::FileEntry;
+use crate::pattern::{glob_match, is_glob};
+use crate::sizes::parse_size;
+use cosmic::iced_widget::{button as cosmic_button, radio};
+use cosmic::{
+    iced::{Alignment, Length},
+    widget::{row, text, text_input},
+    Element,
+};
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KindFilter {
+    #[default]
+    All,
+    Files,
+    Directories,
+}
+
+// Raw text is kept for the inputs, parsed values are what entries get matched against
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    pub name: String,
+    pub min_size: String,
+    pub max_size: String,
+    pub modified_before: String,
+    pub kind: KindFilter,
+    min_bytes: Option<u64>,
+    max_bytes: Option<u64>,
+    before: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Name(String),
+    MinSize(String),
+    MaxSize(String),
+    ModifiedBefore(String),
+    Kind(KindFilter),
+    Clear,
+}
+
+impl FileFilter {
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::Name(name) => self.name = name,
+            Message::MinSize(input) => {
+                self.min_bytes = parse_size(&input);
+                self.min_size = input;
+            }
+            Message::MaxSize(input) => {
+                self.max_bytes = parse_size(&input);
+                self.max_size = input;
+            }
+            Message::ModifiedBefore(input) => {
+                self.before = parse_date(&input);
+                self.modified_before = input;
+            }
+            Message::Kind(kind) => self.kind = kind,
+            Message::Clear => *self = FileFilter::default(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.name.trim().is_empty()
+            || self.min_bytes.is_some()
+            || self.max_bytes.is_some()
+            || self.before.is_some()
+            || self.kind != KindFilter::All
+    }
+
+    pub fn matches(&self, entry: &FileEntry) -> bool {
+        let kind_ok = match self.kind {
+            KindFilter::All => true,
+            KindFilter::Files => !entry.is_dir,
+            KindFilter::Directories => entry.is_dir,
+        };
+        if !kind_ok {
+            return false;
+        }
+
+        if self.min_bytes.is_some_and(|min| entry.size < min) || self.max_bytes.is_some_and(|max| entry.size > max) {
+            return false;
+        }
+
+        if let Some(before) = self.before {
+            // Entries without a timestamp can't be proven old enough
+            if entry.modified.is_none_or(|modified| modified >= before) {
+                return false;
+            }
+        }
+
+        let pattern = self.name.trim().to_lowercase();
+        if pattern.is_empty() {
+            return true;
+        }
+        let name = entry.path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+        if is_glob(&pattern) {
+            glob_match(&pattern, &name)
+        } else {
+            name.contains(&pattern)
+        }
+    }
+
+    pub fn view(&'_ self) -> Element<'_, Message> {
+        let kinds = [
+            (KindFilter::All, "All"),
+            (KindFilter::Files, "Files"),
+            (KindFilter::Directories, "Folders"),
+        ];
+        let mut kind_row = row().spacing(8).align_y(Alignment::Center);
+        for (kind, label) in kinds {
+            kind_row = kind_row.push(radio(label, kind, Some(self.kind), Message::Kind).size(14.0));
+        }
+
+        row()
+            .push(
+                text_input("Name or glob", &self.name)
+                    .on_input(Message::Name)
+                    .width(Length::Fill)
+            )
+            .push(
+                text_input("Min size", &self.min_size)
+                    .on_input(Message::MinSize)
+                    .width(Length::Fixed(90.0))
+            )
+            .push(
+                text_input("Max size", &self.max_size)
+                    .on_input(Message::MaxSize)
+                    .width(Length::Fixed(90.0))
+            )
+            .push(
+                text_input("Before YYYY-MM-DD", &self.modified_before)
+                    .on_input(Message::ModifiedBefore)
+                    .width(Length::Fixed(150.0))
+            )
+            .push(kind_row)
+            .push(cosmic_button(text("Clear")).on_press(Message::Clear).padding([4, 8]))
+            .spacing(8)
+            .align_y(Alignment::Center)
+            .into()
+    }
+}
+
+// Parse "YYYY-MM-DD" as midnight UTC
+fn parse_date(input: &str) -> Option<SystemTime> {
+    let mut parts = input.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    let leap_year = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap_year => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return None;
+    }
+
+    // Days since the epoch from a civil date (Howard Hinnant's algorithm)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = u64::try_from(days).ok()? * 86_400;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+// "YYYY-MM-DD" in UTC, the inverse of parse_date
+pub fn format_date(time: SystemTime) -> String {
+    let days = time.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |since| since.as_secs() / 86_400) as i64;
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z / 146097;
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days(input: &str) -> Option<u64> {
+        parse_date(input).map(|time| time.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() / 86_400)
+    }
+
+    #[test]
+    fn parses_dates_as_days_since_the_epoch() {
+        assert_eq!(days("1970-01-01"), Some(0));
+        assert_eq!(days("2000-03-01"), Some(11017));
+        assert_eq!(days(" 2024-12-31 "), Some(20088));
+    }
+
+    #[test]
+    fn rejects_days_past_the_end_of_the_month() {
+        assert_eq!(days("2024-02-29"), Some(19782));
+        assert_eq!(days("2023-02-29"), None);
+        assert_eq!(days("2024-02-31"), None);
+        assert_eq!(days("2024-04-31"), None);
+        assert_eq!(days("1900-02-29"), None);
+        assert!(days("2000-02-29").is_some());
+    }
+
+    #[test]
+    fn rejects_malformed_dates() {
+        assert_eq!(days(""), None);
+        assert_eq!(days("2024-13-01"), None);
+        assert_eq!(days("2024-00-10"), None);
+        assert_eq!(days("2024-01-00"), None);
+        assert_eq!(days("2024-01"), None);
+        assert_eq!(days("yesterday"), None);
+        assert_eq!(days("1960-01-01"), None);
+    }
+
+    #[test]
+    fn formats_modified_times_as_the_dates_it_parses() {
+        for date in ["1970-01-01", "1999-12-31", "2000-02-29", "2024-03-01", "2038-01-19"] {
+            assert_eq!(format_date(parse_date(date).unwrap()), date);
+        }
+        let evening = parse_date("2024-06-30").unwrap() + std::time::Duration::from_secs(23 * 3600);
+        assert_eq!(format_date(evening), "2024-06-30");
+    }
+}
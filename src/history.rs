@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+// Oldest folders are forgotten past this many steps back
+const MAX_HISTORY: usize = 100;
+
+// A folder that was browsed, with enough to put the view back the way it was left
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub mount_point: String,
+    pub path: String,
+    // Grid scroll offset and the tile that was under the cursor or marked when leaving
+    pub scroll: f32,
+    pub selected: Option<PathBuf>,
+}
+
+// Back and forward stacks like a browser's, covering folders on every partition
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    back: Vec<Location>,
+    forward: Vec<Location>,
+}
+
+impl History {
+    // Leaving for somewhere new drops whatever was ahead
+    pub fn visit(&mut self, from: Location) {
+        self.back.push(from);
+        if self.back.len() > MAX_HISTORY {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    pub fn back(&mut self, from: Location) -> Option<Location> {
+        let to = self.back.pop()?;
+        self.forward.push(from);
+        Some(to)
+    }
+
+    pub fn forward(&mut self, from: Location) -> Option<Location> {
+        let to = self.forward.pop()?;
+        self.back.push(from);
+        Some(to)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(path: &str) -> Location {
+        Location { mount_point: "/".to_string(), path: path.to_string(), scroll: 0.0, selected: None }
+    }
+
+    #[test]
+    fn goes_back_and_forward_through_visited_folders() {
+        let mut history = History::default();
+        assert!(!history.can_go_back() && !history.can_go_forward());
+
+        history.visit(location("/a"));
+        history.visit(location("/b"));
+        assert_eq!(history.back(location("/c")), Some(location("/b")));
+        assert_eq!(history.back(location("/b")), Some(location("/a")));
+        assert_eq!(history.back(location("/a")), None);
+        assert!(history.can_go_forward());
+
+        assert_eq!(history.forward(location("/a")), Some(location("/b")));
+        assert_eq!(history.forward(location("/b")), Some(location("/c")));
+        assert_eq!(history.forward(location("/c")), None);
+        assert_eq!(history.back(location("/c")), Some(location("/b")));
+    }
+
+    #[test]
+    fn visiting_somewhere_new_drops_the_way_forward() {
+        let mut history = History::default();
+        history.visit(location("/a"));
+        history.visit(location("/b"));
+        history.back(location("/c"));
+        assert!(history.can_go_forward());
+
+        history.visit(location("/b"));
+        assert!(!history.can_go_forward());
+        assert_eq!(history.forward(location("/d")), None);
+        assert_eq!(history.back(location("/d")), Some(location("/b")));
+        assert_eq!(history.back(location("/b")), Some(location("/a")));
+    }
+
+    #[test]
+    fn forgets_the_oldest_folders_past_the_limit() {
+        let mut history = History::default();
+        for index in 0..MAX_HISTORY + 5 {
+            history.visit(location(&format!("/{}", index)));
+        }
+        let mut oldest = None;
+        let mut steps = 0;
+        while let Some(previous) = history.back(location("/elsewhere")) {
+            oldest = Some(previous);
+            steps += 1;
+        }
+        assert_eq!(steps, MAX_HISTORY);
+        assert_eq!(oldest, Some(location("/5")));
+    }
+}
@@ -0,0 +1,72 @@
+use nix::errno::Errno;
+use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use std::fs::File;
+use std::os::fd::AsFd;
+use std::thread;
+use std::time::Duration;
+use udev::MonitorBuilder;
+
+// Let a burst of events (a disk and all of its partitions appearing) settle into one rescan
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+// Blocks, calling on_change whenever a block device comes or goes or the mount table changes.
+// Returns once on_change reports that nobody is listening anymore.
+pub fn watch_devices(verbose: bool, mut on_change: impl FnMut() -> bool) {
+    let monitor = match MonitorBuilder::new()
+        .and_then(|builder| builder.match_subsystem("block"))
+        .and_then(|builder| builder.listen())
+    {
+        Ok(monitor) => Some(monitor),
+        Err(e) => {
+            if verbose {
+                println!("Failed to start udev monitor: {:?}", e);
+            }
+            None
+        }
+    };
+
+    // The kernel flags mountinfo with POLLPRI whenever something is mounted or unmounted
+    let mountinfo = File::open("/proc/self/mountinfo").ok();
+
+    if monitor.is_none() && mountinfo.is_none() {
+        return;
+    }
+
+    loop {
+        let mut fds = Vec::new();
+        if let Some(monitor) = &monitor {
+            fds.push(PollFd::new(monitor.as_fd(), PollFlags::POLLIN));
+        }
+        if let Some(mountinfo) = &mountinfo {
+            fds.push(PollFd::new(mountinfo.as_fd(), PollFlags::POLLPRI));
+        }
+
+        match poll(&mut fds, PollTimeout::NONE) {
+            Ok(_) => {}
+            Err(Errno::EINTR) => continue,
+            Err(e) => {
+                if verbose {
+                    println!("Device watcher stopped: {:?}", e);
+                }
+                return;
+            }
+        }
+        drop(fds);
+
+        thread::sleep(SETTLE_TIME);
+        if let Some(monitor) = &monitor {
+            for event in monitor.iter() {
+                if verbose {
+                    println!("udev event: {:?} {:?}", event.event_type(), event.sysname());
+                }
+            }
+        }
+
+        if verbose {
+            println!("Block devices or mounts changed, rescanning");
+        }
+        if !on_change() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::disk::{LayoutSegment, SegmentKind};
+use cosmic::iced::{Alignment, Border, Color, Length, Shadow};
+use crate::sizes::format_size;
+use cosmic::widget::{column, container, row, text, tooltip};
+use cosmic::Element;
+
+// Partition-manager style overview of a disk, every segment sized by its share of the capacity
+#[derive(Debug, Clone)]
+pub struct LayoutBar;
+
+const TOTAL_WIDTH: f32 = 200.0;
+
+// Resolution of the proportional widths, tiny partitions still get a sliver
+const PORTIONS: u64 = 1000;
+
+fn segment_color(is_dark: bool, kind: SegmentKind) -> Color {
+    match (kind, is_dark) {
+        (SegmentKind::Mounted, true) => Color::from_rgb(0.396, 0.592, 0.847), // #6597D8
+        (SegmentKind::Mounted, false) => Color::from_rgb(0.208, 0.396, 0.643), // #3565A4
+        (SegmentKind::Unmounted, true) => Color::from_rgb(0.557, 0.557, 0.557), // #8E8E8E
+        (SegmentKind::Unmounted, false) => Color::from_rgb(0.620, 0.620, 0.620), // #9E9E9E
+        (SegmentKind::Swap, true) => Color::from_rgb(0.839, 0.604, 0.337), // #D69A56
+        (SegmentKind::Swap, false) => Color::from_rgb(0.749, 0.471, 0.153), // #BF7827
+        (SegmentKind::Stacked, true) => Color::from_rgb(0.608, 0.494, 0.804), // #9B7ECD
+        (SegmentKind::Stacked, false) => Color::from_rgb(0.439, 0.314, 0.659), // #7050A8
+        (SegmentKind::Other, true) => Color::from_rgb(0.420, 0.420, 0.420), // #6B6B6B
+        (SegmentKind::Other, false) => Color::from_rgb(0.447, 0.447, 0.447), // #727272
+        (SegmentKind::Free, true) => Color::from_rgb(0.2, 0.2, 0.2), // #333333
+        (SegmentKind::Free, false) => Color::from_rgb(0.9, 0.9, 0.9), // #E6E6E6
+    }
+}
+
+fn kind_label(kind: SegmentKind) -> &'static str {
+    match kind {
+        SegmentKind::Mounted => "Mounted",
+        SegmentKind::Unmounted => "Not mounted",
+        SegmentKind::Swap => "Swap",
+        SegmentKind::Stacked => "Encrypted / LVM / RAID",
+        SegmentKind::Other => "Other",
+        SegmentKind::Free => "Unallocated",
+    }
+}
+
+fn swatch<'a, Message: 'static>(color: Color, width: Length) -> Element<'a, Message> {
+    container(text(""))
+        .width(width)
+        .height(Length::Fill)
+        .style(move |_theme| container::Style {
+            background: Some(color.into()),
+            border: Border {
+                color: Color::TRANSPARENT,
+                width: 0.0,
+                radius: 2.0.into(),
+            },
+            text_color: None,
+            icon_color: None,
+            shadow: Shadow::default(),
+        })
+        .into()
+}
+
+impl LayoutBar {
+    pub fn new<'a, Message: 'static>(is_dark: bool, segments: &[LayoutSegment], capacity: u64) -> Element<'a, Message> {
+        let border_color = if is_dark {
+            Color::from_rgb(0.0, 0.0, 0.0)
+        } else {
+            Color::from_rgb(0.294, 0.294, 0.294) // #4B4B4B
+        };
+
+        let mut bar = row().spacing(1).height(Length::Fill);
+        for segment in segments {
+            let portion = (segment.size.saturating_mul(PORTIONS) / capacity.max(1)).clamp(1, PORTIONS) as u16;
+            // Hovering a segment names it, sda2 - 512.00 GB - Mounted
+            let mut label = format!("{} - {}", format_size(segment.size), kind_label(segment.kind));
+            if !segment.name.is_empty() {
+                label = format!("{} - {}", segment.name, label);
+            }
+            bar = bar.push(tooltip(
+                swatch(segment_color(is_dark, segment.kind), Length::FillPortion(portion)),
+                text(label).size(11.0),
+                tooltip::Position::Bottom,
+            ));
+        }
+
+        container(bar)
+            .width(Length::Fixed(TOTAL_WIDTH))
+            .height(Length::Fixed(14.0))
+            .padding(1)
+            .style(move |_theme| container::Style {
+                background: Some(segment_color(is_dark, SegmentKind::Free).into()),
+                border: Border {
+                    color: border_color,
+                    width: 1.0,
+                    radius: 4.0.into(),
+                },
+                text_color: None,
+                icon_color: None,
+                shadow: Shadow::default(),
+            })
+            .into()
+    }
+
+    // One swatch per kind of segment present on the disk
+    pub fn legend<'a, Message: 'static>(is_dark: bool, segments: &[LayoutSegment]) -> Element<'a, Message> {
+        let mut kinds: Vec<SegmentKind> = Vec::new();
+        for segment in segments {
+            if !kinds.contains(&segment.kind) {
+                kinds.push(segment.kind);
+            }
+        }
+
+        let mut legend = column().spacing(2);
+        for kind in kinds {
+            legend = legend.push(
+                row()
+                    .push(container(swatch(segment_color(is_dark, kind), Length::Fixed(10.0))).height(Length::Fixed(10.0)))
+                    .push(text(kind_label(kind)).size(11.0))
+                    .spacing(3)
+                    .align_y(Alignment::Center)
+            );
+        }
+        legend.into()
+    }
+}
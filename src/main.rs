@@ -4,10 +4,30 @@ use std::env;
 use clap::Parser;
 
 mod files;
+mod config;
 mod disk;
+mod diskstats;
+mod filter;
+mod history;
+mod hotplug;
+mod layout_bar;
+mod mime;
+mod mountinfo;
 mod partition;
+mod network;
+mod pattern;
 mod progress_bar;
+mod reclaim;
+mod search;
 mod sizes;
+mod sunburst;
+mod swaps;
+mod sysfs;
+mod timeout;
+mod tree;
+mod treemap;
+mod udisks;
+mod user_dirs;
 mod ui;
 mod crawler;
 
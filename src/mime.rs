@@ -0,0 +1,442 @@
+use crate::pattern::{glob_match, is_glob};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File, FileType};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+// Loaded on first use and kept for every crawl after it
+static DATABASE: OnceLock<MimeDatabase> = OnceLock::new();
+
+// Magic rules rarely look further into a file than this, ISO 9660 sits just past 32 KiB
+const MAX_MAGIC_READ: usize = 64 * 1024;
+
+// One line of globs2: weight:mime/type:pattern[:flags]
+#[derive(Debug, Clone)]
+struct GlobRule {
+    weight: u32,
+    mime: String,
+    // Already lowercase unless case sensitive
+    pattern: String,
+    case_sensitive: bool,
+}
+
+// One line of a magic section, nested below the previous line with a smaller indent
+#[derive(Debug, Clone)]
+struct MagicRule {
+    indent: usize,
+    offset: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    // How many offsets from the start one to try
+    range: usize,
+}
+
+#[derive(Debug, Clone)]
+struct MagicMatch {
+    priority: u32,
+    mime: String,
+    rules: Vec<MagicRule>,
+}
+
+// Just enough of shared-mime-info to pick an icon for a file: globs on the name first, then magic
+// on the contents for names that say nothing
+#[derive(Debug, Clone, Default)]
+pub struct MimeDatabase {
+    globs: Vec<GlobRule>,
+    magic: Vec<MagicMatch>,
+    icons: HashMap<String, String>,
+    generic_icons: HashMap<String, String>,
+}
+
+// $XDG_DATA_HOME and $XDG_DATA_DIRS, most important first
+fn mime_dirs() -> Vec<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| crate::config::home_dir().map(|home| home.join(".local/share")));
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    data_home
+        .into_iter()
+        .chain(data_dirs.split(':').map(PathBuf::from).filter(|dir| dir.is_absolute()))
+        .map(|dir| dir.join("mime"))
+        .collect()
+}
+
+// 50:text/x-csrc:*.c or 50:text/x-c++src:*.C:cs
+fn parse_globs2(contents: &str) -> Vec<GlobRule> {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, ':');
+            let weight = fields.next()?.parse().ok()?;
+            let mime = fields.next()?.to_string();
+            let pattern = fields.next()?;
+            let case_sensitive = fields.next().is_some_and(|flags| flags.split(',').any(|flag| flag == "cs"));
+            let pattern = if case_sensitive { pattern.to_string() } else { pattern.to_lowercase() };
+            Some(GlobRule { weight, mime, pattern, case_sensitive })
+        })
+        .collect()
+}
+
+// Decimal number at the start of the input and the rest after it
+fn take_number(input: &[u8]) -> Option<(usize, &[u8])> {
+    let digits = input.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&input[..digits]).ok()?.parse().ok()?;
+    Some((number, &input[digits..]))
+}
+
+// Byte-swap every word of a value written in big-endian order so it compares against host data
+fn to_host_order(bytes: &mut [u8], word_size: usize) {
+    if cfg!(target_endian = "little") && word_size > 1 {
+        for word in bytes.chunks_mut(word_size) {
+            word.reverse();
+        }
+    }
+}
+
+// [indent]>offset=<u16 length><value>[&<mask>][~word size][+range]\n
+fn parse_magic_rule(input: &[u8]) -> Option<(MagicRule, &[u8])> {
+    let (indent, rest) = take_number(input).unwrap_or((0, input));
+    let rest = rest.strip_prefix(b">")?;
+    let (offset, rest) = take_number(rest)?;
+    let rest = rest.strip_prefix(b"=")?;
+    let length = u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as usize;
+    let mut value = rest.get(2..2 + length)?.to_vec();
+    let mut rest = &rest[2 + length..];
+
+    let mut mask = None;
+    if let Some(after) = rest.strip_prefix(b"&") {
+        mask = Some(after.get(..length)?.to_vec());
+        rest = &after[length..];
+    }
+    let mut word_size = 1;
+    if let Some(after) = rest.strip_prefix(b"~") {
+        (word_size, rest) = take_number(after)?;
+    }
+    let mut range = 1;
+    if let Some(after) = rest.strip_prefix(b"+") {
+        (range, rest) = take_number(after)?;
+    }
+    // Anything else on the line is from a newer format, skip it rather than misread it
+    let end = rest.iter().position(|&byte| byte == b'\n')?;
+
+    to_host_order(&mut value, word_size);
+    if let Some(mask) = &mut mask {
+        to_host_order(mask, word_size);
+    }
+    Some((MagicRule { indent, offset, value, mask, range: range.max(1) }, &rest[end + 1..]))
+}
+
+// The binary magic file: a header, then [priority:mime/type] sections each followed by their rules
+fn parse_magic(contents: &[u8]) -> Vec<MagicMatch> {
+    let Some(mut rest) = contents.strip_prefix(b"MIME-Magic\0\n") else {
+        return Vec::new();
+    };
+
+    let mut matches: Vec<MagicMatch> = Vec::new();
+    while !rest.is_empty() {
+        if rest[0] == b'[' {
+            let Some(end) = rest.iter().position(|&byte| byte == b'\n') else {
+                break;
+            };
+            let header = String::from_utf8_lossy(&rest[1..end]);
+            if let Some((priority, mime)) = header.trim_end_matches(']').split_once(':') {
+                matches.push(MagicMatch {
+                    priority: priority.parse().unwrap_or(50),
+                    mime: mime.to_string(),
+                    rules: Vec::new(),
+                });
+            }
+            rest = &rest[end + 1..];
+        } else {
+            match parse_magic_rule(rest) {
+                Some((rule, after)) => {
+                    if let Some(current) = matches.last_mut() {
+                        current.rules.push(rule);
+                    }
+                    rest = after;
+                }
+                // Lost track of the format, what was parsed so far is still good
+                None => break,
+            }
+        }
+    }
+    matches.sort_by_key(|magic| std::cmp::Reverse(magic.priority));
+    matches
+}
+
+// mime/type:icon-name
+fn parse_icons(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(mime, icon)| (mime.to_string(), icon.to_string()))
+        .collect()
+}
+
+impl MagicRule {
+    fn matches_at(&self, data: &[u8], start: usize) -> bool {
+        let Some(window) = data.get(start..start + self.value.len()) else {
+            return false;
+        };
+        match &self.mask {
+            Some(mask) => window
+                .iter()
+                .zip(mask)
+                .zip(&self.value)
+                .all(|((byte, mask), value)| byte & mask == value & mask),
+            None => window == self.value.as_slice(),
+        }
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        (self.offset..self.offset + self.range).any(|start| self.matches_at(data, start))
+    }
+}
+
+// A rule holds if it matches and, when it has nested rules, at least one of those holds too
+fn rules_match(rules: &[MagicRule], data: &[u8]) -> bool {
+    let Some(indent) = rules.first().map(|rule| rule.indent) else {
+        return false;
+    };
+    let mut index = 0;
+    while index < rules.len() {
+        let children_end = rules[index + 1..]
+            .iter()
+            .position(|rule| rule.indent <= indent)
+            .map_or(rules.len(), |position| index + 1 + position);
+        let children = &rules[index + 1..children_end];
+        if rules[index].matches(data) && (children.is_empty() || rules_match(children, data)) {
+            return true;
+        }
+        index = children_end;
+    }
+    false
+}
+
+impl GlobRule {
+    fn matches(&self, name: &str, lowercase_name: &str) -> bool {
+        let name = if self.case_sensitive { name } else { lowercase_name };
+        if !is_glob(&self.pattern) {
+            return self.pattern == name;
+        }
+        match self.pattern.strip_prefix('*') {
+            Some(suffix) if !is_glob(suffix) => name.ends_with(suffix),
+            _ => glob_match(&self.pattern, name),
+        }
+    }
+}
+
+impl MimeDatabase {
+    pub fn shared() -> &'static MimeDatabase {
+        DATABASE.get_or_init(MimeDatabase::load)
+    }
+
+    // Everything update-mime-database wrote to the XDG data directories
+    fn load() -> MimeDatabase {
+        let mut database = MimeDatabase::default();
+        for dir in mime_dirs() {
+            if let Ok(contents) = fs::read_to_string(dir.join("globs2")) {
+                database.globs.extend(parse_globs2(&contents));
+            }
+            if let Ok(contents) = fs::read(dir.join("magic")) {
+                database.magic.extend(parse_magic(&contents));
+            }
+            for (file_name, icons) in [("icons", &mut database.icons), ("generic-icons", &mut database.generic_icons)] {
+                if let Ok(contents) = fs::read_to_string(dir.join(file_name)) {
+                    // Earlier directories win
+                    for (mime, icon) in parse_icons(&contents) {
+                        icons.entry(mime).or_insert(icon);
+                    }
+                }
+            }
+        }
+        database.magic.sort_by_key(|magic| std::cmp::Reverse(magic.priority));
+        database
+    }
+
+    // Heaviest matching glob, then case-sensitive ones so *.C beats *.c, then the longest pattern
+    pub fn mime_for_name(&self, name: &str) -> Option<&str> {
+        let lowercase_name = name.to_lowercase();
+        self.globs
+            .iter()
+            .filter(|glob| glob.matches(name, &lowercase_name))
+            .max_by_key(|glob| (glob.weight, glob.case_sensitive, glob.pattern.len()))
+            .map(|glob| glob.mime.as_str())
+    }
+
+    pub fn mime_for_data(&self, data: &[u8]) -> Option<&str> {
+        self.magic
+            .iter()
+            .find(|magic| rules_match(&magic.rules, data))
+            .map(|magic| magic.mime.as_str())
+    }
+
+    // Look at the contents only when the name gives nothing away, and only for regular files.
+    // Opening a FIFO with no writer blocks forever, device nodes and sockets have nothing to sniff.
+    pub fn mime_for_file(&self, path: &Path, file_type: FileType) -> Option<String> {
+        let name = path.file_name()?.to_string_lossy();
+        if let Some(mime) = self.mime_for_name(&name) {
+            return Some(mime.to_string());
+        }
+        if !file_type.is_file() {
+            return None;
+        }
+        let mut data = Vec::new();
+        File::open(path).ok()?.take(MAX_MAGIC_READ as u64).read_to_end(&mut data).ok()?;
+        self.mime_for_data(&data).map(str::to_string)
+    }
+
+    // Icon names for a MIME type, most specific first: video/mp4 gives video-mp4 then video-x-generic
+    pub fn icon_names(&self, mime: &str) -> Vec<String> {
+        let specific = self.icons.get(mime).cloned().unwrap_or_else(|| mime.replace('/', "-"));
+        let generic = self.generic_icons.get(mime).cloned().unwrap_or_else(|| {
+            let media = mime.split('/').next().unwrap_or(mime);
+            format!("{}-x-generic", media)
+        });
+        if specific == generic {
+            vec![specific]
+        } else {
+            vec![specific, generic]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLOBS: &str = "\
+# This file was automatically generated by the update-mime-database command. DO NOT EDIT!
+50:text/x-csrc:*.c
+50:text/x-c++src:*.C:cs
+50:application/x-compressed-tar:*.tar.gz
+10:application/gzip:*.gz
+60:text/x-makefile:makefile
+not a glob line
+";
+
+    // Indent, offset, value and whatever follows it (mask, word size, range)
+    type Rule<'a> = (usize, usize, &'a [u8], &'a [u8]);
+
+    // One magic section per priority and type
+    fn magic_file(sections: &[(u32, &str, &[Rule])]) -> Vec<u8> {
+        let mut contents = b"MIME-Magic\0\n".to_vec();
+        for (priority, mime, rules) in sections {
+            contents.extend(format!("[{}:{}]\n", priority, mime).bytes());
+            for (indent, offset, value, extra) in rules.iter() {
+                if *indent > 0 {
+                    contents.extend(indent.to_string().bytes());
+                }
+                contents.extend(format!(">{}=", offset).bytes());
+                contents.extend((value.len() as u16).to_be_bytes());
+                contents.extend_from_slice(value);
+                contents.extend_from_slice(extra);
+                contents.push(b'\n');
+            }
+        }
+        contents
+    }
+
+    fn database(magic: &[u8]) -> MimeDatabase {
+        MimeDatabase { globs: parse_globs2(GLOBS), magic: parse_magic(magic), ..MimeDatabase::default() }
+    }
+
+    #[test]
+    fn parses_globs2_lines() {
+        let globs = parse_globs2(GLOBS);
+        assert_eq!(globs.len(), 5);
+        assert_eq!((globs[0].weight, globs[0].mime.as_str(), globs[0].pattern.as_str()), (50, "text/x-csrc", "*.c"));
+        assert!(!globs[0].case_sensitive);
+        assert_eq!(globs[1].pattern, "*.C");
+        assert!(globs[1].case_sensitive);
+    }
+
+    #[test]
+    fn picks_the_heaviest_then_longest_glob() {
+        let database = database(&[]);
+        assert_eq!(database.mime_for_name("main.c"), Some("text/x-csrc"));
+        assert_eq!(database.mime_for_name("MAIN.C"), Some("text/x-c++src"));
+        assert_eq!(database.mime_for_name("backup.tar.gz"), Some("application/x-compressed-tar"));
+        assert_eq!(database.mime_for_name("notes.GZ"), Some("application/gzip"));
+        assert_eq!(database.mime_for_name("Makefile"), Some("text/x-makefile"));
+        assert_eq!(database.mime_for_name("README"), None);
+    }
+
+    #[test]
+    fn matches_magic_with_nested_rules_masks_and_ranges() {
+        let magic = magic_file(&[
+            (50, "application/pdf", &[(0, 0, b"%PDF-", b"")]),
+            (80, "application/x-nested", &[(0, 0, b"AB", b""), (1, 4, b"CD", b"")]),
+            (40, "application/x-masked", &[(0, 0, b"\x50\x00", b"&\xf0\x00")]),
+            (30, "application/x-ranged", &[(0, 2, b"XYZ", b"+8")]),
+        ]);
+        let database = database(&magic);
+
+        assert_eq!(database.magic.iter().map(|magic| magic.priority).collect::<Vec<_>>(), [80, 50, 40, 30]);
+        assert_eq!(database.mime_for_data(b"%PDF-1.7"), Some("application/pdf"));
+        assert_eq!(database.mime_for_data(b"AB..CD"), Some("application/x-nested"));
+        assert_eq!(database.mime_for_data(b"AB..EF"), None);
+        assert_eq!(database.mime_for_data(b"\x5f\x99"), Some("application/x-masked"));
+        assert_eq!(database.mime_for_data(b"......XYZ"), Some("application/x-ranged"));
+        assert_eq!(database.mime_for_data(b"............XYZ"), None);
+    }
+
+    #[test]
+    fn byte_swaps_values_with_a_word_size() {
+        let database = database(&magic_file(&[(50, "application/x-swapped", &[(0, 0, b"\x12\x34", b"~2")])]));
+        let host_order = 0x1234u16.to_ne_bytes();
+        assert_eq!(database.mime_for_data(&host_order), Some("application/x-swapped"));
+    }
+
+    #[test]
+    fn keeps_what_was_parsed_before_a_broken_rule() {
+        let mut magic = magic_file(&[(50, "application/pdf", &[(0, 0, b"%PDF-", b"")])]);
+        magic.extend(b"[50:application/x-broken]\n>0=\x00\x09short");
+        let database = database(&magic);
+        assert_eq!(database.magic.len(), 2);
+        assert_eq!(database.mime_for_data(b"%PDF-1.4"), Some("application/pdf"));
+        assert!(parse_magic(b"not magic").is_empty());
+    }
+
+    #[test]
+    fn sniffs_regular_files_but_never_opens_fifos() {
+        let dir = std::env::temp_dir().join(format!("cosmic-dust-mime-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let document = dir.join("document");
+        fs::write(&document, b"%PDF-1.7").unwrap();
+        let pipe = dir.join("pipe");
+        nix::unistd::mkfifo(&pipe, nix::sys::stat::Mode::S_IRWXU).unwrap();
+
+        let database = database(&magic_file(&[(50, "application/pdf", &[(0, 0, b"%PDF-", b"")])]));
+        let file_type = |path: &Path| fs::symlink_metadata(path).unwrap().file_type();
+        assert_eq!(database.mime_for_file(&document, file_type(&document)).as_deref(), Some("application/pdf"));
+
+        // A FIFO nobody writes to would block the read, run it where a hang can't stall the tests
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let pipe_type = file_type(&pipe);
+        let fifo = pipe.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(database.mime_for_file(&fifo, pipe_type));
+        });
+        let result = receiver.recv_timeout(std::time::Duration::from_secs(5));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn falls_back_to_generic_icon_names() {
+        let mut database = database(&[]);
+        assert_eq!(database.icon_names("video/mp4"), ["video-mp4", "video-x-generic"]);
+        database.generic_icons.insert("application/pdf".to_string(), "x-office-document".to_string());
+        assert_eq!(database.icon_names("application/pdf"), ["application-pdf", "x-office-document"]);
+    }
+}
@@ -0,0 +1,170 @@
+use std::fs;
+
+// One line of /proc/self/mountinfo, see proc_pid_mountinfo(5)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountInfo {
+    pub mount_id: u32,
+    pub parent_id: u32,
+    pub major: u64,
+    pub minor: u64,
+    // Directory of the filesystem mounted here, "/" unless this is a bind or subvolume mount
+    pub root: String,
+    pub mount_point: String,
+    pub mount_options: String,
+    pub fs_type: String,
+    pub source: String,
+    pub super_options: String,
+}
+
+impl MountInfo {
+    // Bind mounts and btrfs subvolumes expose a subdirectory of their filesystem
+    pub fn is_subtree(&self) -> bool {
+        self.root != "/"
+    }
+
+    // Per-mount options followed by filesystem options, e.g. ["rw", "noatime", "compress=zstd:3"].
+    // Both lists usually carry "rw" or "ro", it's listed once.
+    pub fn options(&self) -> Vec<&str> {
+        let mut options: Vec<&str> = Vec::new();
+        for option in self.mount_options.split(',').chain(self.super_options.split(',')) {
+            if !option.is_empty() && !options.contains(&option) {
+                options.push(option);
+            }
+        }
+        options
+    }
+
+    // Either the mount or the filesystem itself can be read-only, ext4 with errors=remount-ro
+    // flips only the superblock options after an I/O error
+    pub fn is_read_only(&self) -> bool {
+        self.mount_options.split(',').chain(self.super_options.split(',')).any(|option| option == "ro")
+    }
+}
+
+pub fn read_mountinfo() -> Vec<MountInfo> {
+    fs::read_to_string("/proc/self/mountinfo")
+        .map(|contents| parse_mountinfo(&contents))
+        .unwrap_or_default()
+}
+
+pub fn parse_mountinfo(contents: &str) -> Vec<MountInfo> {
+    contents.lines().filter_map(parse_line).collect()
+}
+
+// 36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue
+fn parse_line(line: &str) -> Option<MountInfo> {
+    let mut fields = line.split(' ');
+    let mount_id = fields.next()?.parse().ok()?;
+    let parent_id = fields.next()?.parse().ok()?;
+    let (major, minor) = fields.next()?.split_once(':')?;
+    let root = unescape(fields.next()?);
+    let mount_point = unescape(fields.next()?);
+    let mount_options = fields.next()?.to_string();
+
+    // Zero or more optional fields (shared:N, master:N...) end with a lone "-"
+    fields.by_ref().find(|field| *field == "-")?;
+
+    let fs_type = unescape(fields.next()?);
+    let source = unescape(fields.next()?);
+    let super_options = fields.next().unwrap_or_default().to_string();
+
+    Some(MountInfo {
+        mount_id,
+        parent_id,
+        major: major.parse().ok()?,
+        minor: minor.parse().ok()?,
+        root,
+        mount_point,
+        mount_options,
+        fs_type,
+        source,
+        super_options,
+    })
+}
+
+// The kernel writes space, tab, newline and backslash as octal escapes (\040, \011, \012, \134)
+pub fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escape = bytes.get(index + 1..index + 4).filter(|digits| {
+            bytes[index] == b'\\' && digits.iter().all(|digit| (b'0'..=b'7').contains(digit))
+        });
+        match escape {
+            Some(digits) => {
+                let value = digits.iter().fold(0u32, |value, digit| value * 8 + (digit - b'0') as u32);
+                decoded.push(value as u8);
+                index += 4;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_every_optional_field_before_the_separator() {
+        let mount = parse_line(
+            "36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 shared:7 propagate_from:2 unbindable - ext3 /dev/root rw,errors=continue"
+        ).unwrap();
+        assert_eq!(mount.mount_id, 36);
+        assert_eq!(mount.parent_id, 35);
+        assert_eq!((mount.major, mount.minor), (98, 0));
+        assert_eq!(mount.root, "/mnt1");
+        assert_eq!(mount.mount_point, "/mnt2");
+        assert_eq!(mount.mount_options, "rw,noatime");
+        assert_eq!(mount.fs_type, "ext3");
+        assert_eq!(mount.source, "/dev/root");
+        assert_eq!(mount.super_options, "rw,errors=continue");
+        assert!(mount.is_subtree());
+    }
+
+    #[test]
+    fn parses_a_line_without_optional_fields() {
+        let mount = parse_line("22 1 8:2 / / rw,relatime - btrfs /dev/sda2 rw,compress=zstd:3").unwrap();
+        assert_eq!(mount.mount_point, "/");
+        assert!(!mount.is_subtree());
+        assert_eq!(mount.options(), ["rw", "relatime", "compress=zstd:3"]);
+    }
+
+    #[test]
+    fn reads_read_only_from_either_option_list() {
+        let writable = parse_line("22 1 8:2 / / rw,relatime - ext4 /dev/sda2 rw,errors=remount-ro").unwrap();
+        assert!(!writable.is_read_only());
+        let remounted = parse_line("22 1 8:2 / / rw,relatime - ext4 /dev/sda2 ro,errors=remount-ro").unwrap();
+        assert!(remounted.is_read_only());
+        let mounted_ro = parse_line("23 1 8:3 / /boot ro,relatime - ext4 /dev/sda3 rw").unwrap();
+        assert!(mounted_ro.is_read_only());
+    }
+
+    #[test]
+    fn rejects_a_line_without_the_separator() {
+        assert_eq!(parse_line("36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 ext3 /dev/root rw"), None);
+        assert_eq!(parse_line(""), None);
+    }
+
+    #[test]
+    fn decodes_escaped_mount_points() {
+        let mount = parse_line(
+            "40 22 8:17 / /media/My\\040Disk\\011Tab\\012Line\\134Slash rw - vfat /dev/sdb1 rw"
+        ).unwrap();
+        assert_eq!(mount.mount_point, "/media/My Disk\tTab\nLine\\Slash");
+    }
+
+    #[test]
+    fn leaves_incomplete_escapes_alone() {
+        assert_eq!(unescape("a\\04"), "a\\04");
+        assert_eq!(unescape("a\\089"), "a\\089");
+        assert_eq!(unescape("trailing\\"), "trailing\\");
+    }
+}
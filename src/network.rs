@@ -0,0 +1,66 @@
+use crate::config::read_lines;
+use crate::pattern::glob_match;
+
+// Filesystem types listed as network drives, with the icon shown for them.
+// Format: <fstype, may use "*" and "?"> [<icon name>]
+const DEFAULT_NETWORK_FILESYSTEMS: &str = "\
+nfs* folder-remote
+cifs network-workgroup
+smb3 network-workgroup
+smbfs network-workgroup
+fuse.sshfs network-server
+fuse.rclone folder-cloud
+davfs folder-remote
+fuse.davfs2 folder-remote
+9p computer
+virtiofs computer
+ceph network-server
+fuse.ceph network-server
+glusterfs network-server
+fuse.glusterfs network-server
+";
+
+// Users can add more types in the same format, their entries take precedence
+const NETWORK_FILESYSTEMS_FILE: &str = "network-filesystems";
+
+const DEFAULT_ICON: &str = "folder-remote";
+
+#[derive(Debug, Clone)]
+struct NetworkFsRule {
+    pattern: String,
+    icon_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkFilesystems {
+    rules: Vec<NetworkFsRule>,
+}
+
+impl NetworkFilesystems {
+    pub fn load(verbose: bool) -> Self {
+        let rules: Vec<NetworkFsRule> = read_lines(NETWORK_FILESYSTEMS_FILE, verbose)
+            .into_iter()
+            .chain(DEFAULT_NETWORK_FILESYSTEMS.lines().map(str::to_string))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?.to_string();
+                let icon_name = fields.next().unwrap_or(DEFAULT_ICON).to_string();
+                Some(NetworkFsRule { pattern, icon_name })
+            })
+            .collect();
+
+        if verbose {
+            println!("Recognizing {} network filesystem types", rules.len());
+        }
+
+        NetworkFilesystems { rules }
+    }
+
+    // Icon for a network filesystem type, None for anything that isn't one
+    pub fn icon_for(&self, fstype: &str) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|rule| glob_match(&rule.pattern, fstype))
+            .map(|rule| rule.icon_name.as_str())
+    }
+}
@@ -1,18 +1,35 @@
-use crate::disk::Drive;
+use crate::disk::{BlockLayer, DiskInfo, Drive, LayerKind, PartitionInfo, UnmountedFilesystem};
+use crate::diskstats::{DiskActivity, DiskStatsSampler};
+use crate::layout_bar::LayoutBar;
+use crate::swaps::SwapArea;
 use crate::progress_bar::ProgressBar;
 use crate::sizes::format_size;
 use cosmic::iced_widget::button as cosmic_button;
 use cosmic::{
-    iced::Alignment,
+    iced::{Alignment, Border, Color, Length},
     theme,
-    widget::{column, icon, row, text},
+    widget::{column, container, icon, row, text},
     Element,
 };
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub struct DiskState {
     pub drives: Vec<Drive>,
     pub expanded: Vec<bool>,
+    // Potentially reclaimable bytes per mount point, known once a partition has been crawled
+    pub reclaimable: HashMap<String, u64>,
+    // Device of the partition whose details panel is open
+    pub details: Option<String>,
+    // Devices this app mounted through UDisks2, only those get an unmount button
+    pub mounted_by_us: HashSet<String>,
+    pub mount_error: Option<String>,
+    io_sampler: DiskStatsSampler,
+    // Throughput per kernel device name over the last sampling interval
+    pub io_activity: HashMap<String, DiskActivity>,
+    // Loop devices are tucked away in their own section, collapsed by default
+    pub images_expanded: bool,
 }
 
 impl DiskState {
@@ -20,19 +37,373 @@ impl DiskState {
         DiskState {
             drives: drives.clone(),
             expanded: vec![false; drives.len()],
+            reclaimable: HashMap::new(),
+            details: None,
+            mounted_by_us: HashSet::new(),
+            mount_error: None,
+            io_sampler: DiskStatsSampler::default(),
+            io_activity: HashMap::new(),
+            images_expanded: false,
         }
     }
 
+    // Swap in a fresh scan while keeping expanded disks open
+    pub fn update_drives(&mut self, drives: Vec<Drive>) {
+        let expanded = drives
+            .iter()
+            .map(|drive| {
+                self.drives
+                    .iter()
+                    .zip(&self.expanded)
+                    .any(|(old, &expanded)| expanded && old.key() == drive.key())
+            })
+            .collect();
+        self.drives = drives;
+        self.expanded = expanded;
+        self.forget_idle_io();
+    }
+
     pub fn toggle(&mut self, index: usize) {
         if let Some(expanded) = self.expanded.get_mut(index) {
             *expanded = !*expanded;
         }
+        self.forget_idle_io();
+    }
+
+    // Throughput is only shown on expanded disks, with none open there's nothing to sample
+    pub fn watching_io(&self) -> bool {
+        self.expanded.iter().any(|&expanded| expanded)
+    }
+
+    // Once sampling stops the last counters go stale, the next disk opened starts from scratch
+    // rather than averaging over however long everything was closed
+    fn forget_idle_io(&mut self) {
+        if !self.watching_io() {
+            self.io_sampler = DiskStatsSampler::default();
+            self.io_activity.clear();
+        }
+    }
+
+    pub fn sample_io(&mut self) {
+        self.io_activity = self.io_sampler.sample();
+    }
+
+    // Dot that lights up while the disk is busy, followed by read and write rates
+    fn activity_view(activity: DiskActivity, is_dark: bool) -> Element<'static, Message> {
+        let dot_color = if activity.is_busy() {
+            Color::from_rgb(0.298, 0.686, 0.314) // #4CAF50
+        } else if is_dark {
+            Color::from_rgb(0.3, 0.3, 0.3) // #4D4D4D
+        } else {
+            Color::from_rgb(0.8, 0.8, 0.8) // #CCCCCC
+        };
+        let dot = container(text(""))
+            .width(Length::Fixed(8.0))
+            .height(Length::Fixed(8.0))
+            .style(move |_theme| container::Style {
+                background: Some(dot_color.into()),
+                border: Border {
+                    color: Color::TRANSPARENT,
+                    width: 0.0,
+                    radius: 4.0.into(),
+                },
+                ..Default::default()
+            });
+        row()
+            .push(dot)
+            .push(text(format!(
+                "R {}/s  W {}/s",
+                format_size(activity.read_per_sec),
+                format_size(activity.write_per_sec)
+            )).size(11.0))
+            .spacing(4)
+            .align_y(Alignment::Center)
+            .into()
+    }
+
+    pub fn toggle_details(&mut self, device: String) {
+        self.details = if self.details.as_ref() == Some(&device) {
+            None
+        } else {
+            Some(device)
+        };
+    }
+
+    fn partition_button(&'_ self, partition: &'_ PartitionInfo) -> Element<'_, Message> {
+        if !partition.responding {
+            // Same as a dead network mount, a crawl would hang on it
+            return row()
+                .push(
+                    column()
+                        .spacing(2)
+                        .push(text(&partition.device))
+                        .push(text(&partition.mount_point).size(12.0))
+                        .push(text("Not responding").size(12.0))
+                )
+                .push(
+                    cosmic_button(icon::from_name("view-refresh-symbolic").size(16))
+                        .on_press(Message::Retry)
+                        .padding([4, 4])
+                )
+                .spacing(4)
+                .padding([4, 8])
+                .align_y(Alignment::Center)
+                .into();
+        }
+        let percent = (partition.used_space as f32 / partition.total_space.max(1) as f32) * 100.0;
+        let is_dark = theme::active().theme_type.is_dark();
+        let mut title = row()
+            .push(text(&partition.device))
+            .spacing(4)
+            .align_y(Alignment::Center);
+        if partition.read_only {
+            title = title.push(text("read-only").size(12.0));
+        }
+        let mut details = column()
+            .spacing(2)
+            .align_x(Alignment::Center)
+            .push(title)
+            .push(ProgressBar::new(is_dark, percent))
+            .push(text(format!(
+                "{} / {}",
+                format_size(partition.used_space),
+                format_size(partition.total_space)
+            )))
+            .push(text(format!("{} available to you", format_size(partition.available_space))).size(12.0));
+        if partition.reserved_space > 0 {
+            details = details.push(text(format!("{} reserved for root", format_size(partition.reserved_space))).size(12.0));
+        }
+        if partition.total_inodes > 0 {
+            details = details.push(text(format!(
+                "Inodes: {} / {} used",
+                partition.used_inodes, partition.total_inodes
+            )).size(12.0));
+        }
+        if partition.inodes_nearly_full() {
+            details = details.push(
+                row()
+                    .push(icon::from_name("dialog-warning-symbolic").size(12))
+                    .push(text("Running out of inodes, new files may fail").size(12.0))
+                    .spacing(4)
+                    .align_y(Alignment::Center)
+            );
+        }
+        if let Some(reclaimable) = self.reclaimable.get(&partition.mount_point) {
+            details = details.push(text(format!("Reclaimable: {}", format_size(*reclaimable))).size(12.0));
+        }
+        let button = cosmic_button(details)
+            .on_press(Message::SelectPartition(partition.mount_point.clone()))
+            .padding([6, 8]);
+        let details_button = cosmic_button(icon::from_name("dialog-information-symbolic").size(16))
+            .on_press(Message::ToggleDetails(partition.device.clone()))
+            .padding([4, 4]);
+        let mut buttons = row()
+            .push(button)
+            .push(details_button)
+            .spacing(4)
+            .align_y(Alignment::Center);
+        if self.mounted_by_us.contains(&partition.device) {
+            buttons = buttons.push(
+                cosmic_button(icon::from_name("media-eject-symbolic").size(16))
+                    .on_press(Message::Unmount(partition.device.clone()))
+                    .padding([4, 4])
+            );
+        }
+        let mut partition_column = column().spacing(2).push(buttons);
+        if self.details.as_ref() == Some(&partition.device) {
+            partition_column = partition_column.push(Self::details_view(partition));
+        }
+        if let Some(others) = Self::other_mount_points_view(&partition.other_mount_points) {
+            partition_column = partition_column.push(others);
+        }
+        partition_column.into()
+    }
+
+    fn details_view(partition: &'_ PartitionInfo) -> Element<'_, Message> {
+        let field = |name: &str, value: String| {
+            row()
+                .push(text(format!("{}:", name)).size(12.0).width(Length::Fixed(90.0)))
+                .push(text(value).size(12.0))
+                .spacing(4)
+        };
+        let unset = || "-".to_string();
+
+        let mut fields = column()
+            .spacing(2)
+            .push(field("Label", partition.label.clone().unwrap_or_else(unset)))
+            .push(field("Partition", partition.part_label.clone().unwrap_or_else(unset)))
+            .push(field("UUID", partition.uuid.clone().unwrap_or_else(unset)))
+            .push(field("Filesystem", partition.file_system.clone()))
+            .push(field("Mounted at", partition.mount_point.clone()))
+            .push(field("Options", partition.mount_options.join(", ")));
+        if partition.read_only {
+            fields = fields.push(
+                row()
+                    .push(icon::from_name("changes-prevent-symbolic").size(12))
+                    .push(text("Mounted read-only").size(12.0))
+                    .spacing(4)
+                    .align_y(Alignment::Center)
+            );
+        }
+        container(fields).padding([4, 8]).into()
+    }
+
+    // Swap has nothing to browse, so this is a plain row instead of a button
+    fn swap_view(area: &'_ SwapArea) -> Element<'_, Message> {
+        let percent = (area.used as f32 / area.size.max(1) as f32) * 100.0;
+        let is_dark = theme::active().theme_type.is_dark();
+        container(
+            column()
+                .spacing(2)
+                .align_x(Alignment::Center)
+                .push(text(format!("{} - swap", area.path.display())))
+                .push(ProgressBar::new(is_dark, percent))
+                .push(text(format!(
+                    "{} / {} in use, priority {}",
+                    format_size(area.used),
+                    format_size(area.size),
+                    area.priority
+                )).size(12.0))
+        )
+            .padding([6, 8])
+            .into()
+    }
+
+    // Not mounted, so there's nothing to scan until the user mounts it. The summary is a button
+    // without an action, which draws it greyed out.
+    fn unmounted_view(filesystem: &'_ UnmountedFilesystem) -> Element<'_, Message> {
+        let name = match &filesystem.label {
+            Some(label) => format!("{} ({})", filesystem.device, label),
+            None => filesystem.device.clone(),
+        };
+        let summary = cosmic_button(
+            column()
+                .spacing(2)
+                .align_x(Alignment::Center)
+                .push(text(name))
+                .push(text(format!("{} - {} - not mounted", filesystem.file_system, format_size(filesystem.size))).size(12.0))
+        )
+            .padding([6, 8]);
+        row()
+            .push(summary)
+            .push(
+                cosmic_button(text("Mount and scan").size(12.0))
+                    .on_press(Message::Mount(filesystem.device.clone()))
+                    .padding([4, 8])
+            )
+            .spacing(4)
+            .align_y(Alignment::Center)
+            .into()
+    }
+
+    // The same filesystem mounted at other places, any of them can be scanned instead
+    fn other_mount_points_view(mount_points: &'_ [String]) -> Option<Element<'_, Message>> {
+        if mount_points.is_empty() {
+            return None;
+        }
+        let mut list = column()
+            .spacing(2)
+            .push(text("Also mounted at").size(12.0));
+        for mount_point in mount_points {
+            list = list.push(
+                cosmic_button(text(mount_point).size(12.0))
+                    .on_press(Message::SelectPartition(mount_point.clone()))
+                    .padding([2, 8])
+            );
+        }
+        Some(list.padding([0, 8]).into())
+    }
+
+    // LUKS containers, volume groups and arrays, indented one step per level of nesting
+    fn layer_view(&'_ self, layer: &'_ BlockLayer, depth: u16) -> Element<'_, Message> {
+        let icon_name = match layer.kind {
+            LayerKind::Luks => "changes-prevent-symbolic",
+            LayerKind::VolumeGroup | LayerKind::Raid => "drive-multidisk",
+            LayerKind::LogicalVolume | LayerKind::DeviceMapper => "drive-harddisk",
+        };
+        let kind_label = match layer.kind {
+            LayerKind::Luks => "Encrypted",
+            LayerKind::VolumeGroup => "Volume group",
+            LayerKind::LogicalVolume => "Logical volume",
+            LayerKind::Raid => "RAID array",
+            LayerKind::DeviceMapper => "Mapped device",
+        };
+        // Volume groups have no device node of their own
+        let kind_label = if layer.device.is_empty() {
+            kind_label.to_string()
+        } else {
+            format!("{} - {}", kind_label, layer.device)
+        };
+        let description = if layer.shown_elsewhere {
+            format!("{} - continued on another disk", kind_label)
+        } else {
+            kind_label
+        };
+
+        let mut layer_column = column()
+            .spacing(4)
+            .push(
+                row()
+                    .push(icon::from_name(icon_name).size(16))
+                    .push(
+                        column()
+                            .push(text(&layer.name))
+                            .push(text(description).size(12.0))
+                    )
+                    .spacing(4)
+                    .align_y(Alignment::Center)
+            );
+        if let Some(filesystem) = &layer.filesystem {
+            layer_column = layer_column.push(self.partition_button(filesystem));
+        }
+        if let Some(filesystem) = &layer.unmounted {
+            layer_column = layer_column.push(Self::unmounted_view(filesystem));
+        }
+        if let Some(area) = &layer.swap {
+            layer_column = layer_column.push(Self::swap_view(area));
+        }
+        for child in &layer.children {
+            layer_column = layer_column.push(self.layer_view(child, depth + 1));
+        }
+
+        let indent = 8.0 + depth as f32 * 12.0;
+        row()
+            .push(container(text("")).width(Length::Fixed(indent)))
+            .push(layer_column)
+            .into()
+    }
+
+    // A mounted disk image or snap, with a shortcut to the file behind it
+    fn image_view(&'_ self, disk: &'_ DiskInfo, backing_file: &'_ Path) -> Element<'_, Message> {
+        let header = row()
+            .push(icon::from_name(&*disk.icon_name).size(24))
+            .push(
+                column()
+                    .spacing(2)
+                    .push(text(&disk.model))
+                    .push(text(backing_file.to_string_lossy()).size(12.0))
+            )
+            .push(
+                cosmic_button(icon::from_name("folder-open-symbolic").size(16))
+                    .on_press(Message::RevealFile(backing_file.to_path_buf()))
+                    .padding([4, 4])
+            )
+            .spacing(4)
+            .align_y(Alignment::Center);
+
+        let mut image = column().spacing(4).push(header);
+        for filesystem in disk.filesystems() {
+            image = image.push(self.partition_button(filesystem));
+        }
+        container(image).padding([0, 8]).into()
     }
 
     pub fn view(&'_ self) -> Element<'_, Message> {
         let mut disk_tree = column().spacing(8);
+        let mut images = Vec::new();
         for (i, drive) in self.drives.iter().enumerate() {
             match drive {
+                Drive::Local(disk) if disk.backing_file.is_some() => images.push(disk),
                 Drive::Local(disk) => {
                     if disk.is_cdrom {
                         let cdrom = &disk.partitions[0];
@@ -57,10 +428,24 @@ impl DiskState {
                             .padding([4, 8]);
                         disk_tree = disk_tree.push(cdrom_row);
                     } else {
+                        let is_dark = theme::active().theme_type.is_dark();
+                        let mut disk_summary = column()
+                            .spacing(2)
+                            .push(text(&disk.model));
+                        if disk.capacity > 0 {
+                            disk_summary = disk_summary
+                                .push(text(format!("{} - {}", disk.device, format_size(disk.capacity))).size(12.0))
+                                .push(LayoutBar::new(is_dark, &disk.layout, disk.capacity));
+                        }
+                        let expanded = self.expanded.get(i).copied().unwrap_or(false);
+                        let kernel_name = Path::new(&disk.device).file_name().map(|name| name.to_string_lossy().to_string());
+                        if let Some(activity) = kernel_name.filter(|_| expanded).and_then(|name| self.io_activity.get(&name)) {
+                            disk_summary = disk_summary.push(Self::activity_view(*activity, is_dark));
+                        }
                         let disk_row = cosmic_button(
                             row()
                                 .push(icon::from_name(&*disk.icon_name).size(24))
-                                .push(text(&disk.model))
+                                .push(disk_summary)
                                 .spacing(4)
                                 .align_y(Alignment::Center),
                         )
@@ -68,43 +453,62 @@ impl DiskState {
                             .padding([4, 8]);
                         disk_tree = disk_tree.push(disk_row);
 
-                        if self.expanded.get(i).copied().unwrap_or(false) {
+                        if expanded {
                             let mut partition_list = column().spacing(4);
-                            for partition in &disk.partitions {
-                                let percent = (partition.used_space as f32 / partition.total_space.max(1) as f32) * 100.0;
-                                let is_dark = theme::active().theme_type.is_dark();
+                            if !disk.layout.is_empty() {
                                 partition_list = partition_list.push(
-                                    cosmic_button(
-                                        column()
-                                            .spacing(2)
-                                            .align_x(Alignment::Center)
-                                            .push(text(&partition.device))
-                                            .push(ProgressBar::new(is_dark, percent))
-                                            .push(text(format!(
-                                                "{} / {}",
-                                                format_size(partition.used_space),
-                                                format_size(partition.total_space)
-                                            )))
-                                    )
-                                        .on_press(Message::SelectPartition(partition.mount_point.clone()))
-                                        .padding([6, 8])
+                                    container(LayoutBar::legend(is_dark, &disk.layout)).padding([0, 8])
                                 );
                             }
+                            for partition in &disk.partitions {
+                                partition_list = partition_list.push(self.partition_button(partition));
+                            }
+                            for layer in &disk.layers {
+                                partition_list = partition_list.push(self.layer_view(layer, 0));
+                            }
+                            for area in &disk.swaps {
+                                partition_list = partition_list.push(Self::swap_view(area));
+                            }
+                            for filesystem in &disk.unmounted {
+                                partition_list = partition_list.push(Self::unmounted_view(filesystem));
+                            }
                             disk_tree = disk_tree.push(partition_list);
                         }
                     }
                 }
+                Drive::Network(net) if !net.responding => {
+                    // Don't let a click on a dead mount start a crawl that would hang
+                    let net_row = row()
+                        .push(icon::from_name(&*net.icon_name).size(24))
+                        .push(
+                            column()
+                                .spacing(2)
+                                .push(text(&net.mount_point))
+                                .push(text(format!("{} ({})", net.source, net.file_system)).size(12.0))
+                                .push(text("Not responding").size(12.0))
+                        )
+                        .push(
+                            cosmic_button(icon::from_name("view-refresh-symbolic").size(16))
+                                .on_press(Message::Retry)
+                                .padding([4, 4])
+                        )
+                        .spacing(4)
+                        .padding([4, 8])
+                        .align_y(Alignment::Center);
+                    disk_tree = disk_tree.push(net_row);
+                }
                 Drive::Network(net) => {
                     let percent = (net.used_space as f32 / net.total_space.max(1) as f32) * 100.0;
                     let is_dark = theme::active().theme_type.is_dark();
                     let net_row = cosmic_button(
                         row()
-                            .push(icon::from_name("network-workgroup").size(24))
+                            .push(icon::from_name(&*net.icon_name).size(24))
                             .push(
                                 column()
                                     .spacing(2)
                                     .align_x(Alignment::Center)
                                     .push(text(&net.mount_point))
+                                    .push(text(format!("{} ({})", net.source, net.file_system)).size(12.0))
                                     .push(ProgressBar::new(is_dark, percent))
                                     .push(text(format!(
                                         "{} / {}",
@@ -118,9 +522,45 @@ impl DiskState {
                         .on_press(Message::SelectPartition(net.mount_point.clone()))
                         .padding([4, 8]);
                     disk_tree = disk_tree.push(net_row);
+                    if let Some(others) = Self::other_mount_points_view(&net.other_mount_points) {
+                        disk_tree = disk_tree.push(others);
+                    }
+                }
+            }
+        }
+
+        if !images.is_empty() {
+            let section_icon = if self.images_expanded { "go-down-symbolic" } else { "go-next-symbolic" };
+            disk_tree = disk_tree.push(
+                cosmic_button(
+                    row()
+                        .push(icon::from_name(section_icon).size(16))
+                        .push(text(format!("Images & snaps ({})", images.len())))
+                        .spacing(4)
+                        .align_y(Alignment::Center),
+                )
+                    .on_press(Message::ToggleImages)
+                    .padding([4, 8])
+            );
+            if self.images_expanded {
+                for disk in images {
+                    if let Some(backing_file) = &disk.backing_file {
+                        disk_tree = disk_tree.push(self.image_view(disk, backing_file));
+                    }
                 }
             }
         }
+
+        if let Some(error) = &self.mount_error {
+            disk_tree = disk_tree.push(
+                row()
+                    .push(icon::from_name("dialog-error-symbolic").size(16))
+                    .push(text(error).size(12.0))
+                    .spacing(4)
+                    .padding([4, 8])
+                    .align_y(Alignment::Center)
+            );
+        }
         disk_tree.into()
     }
 }
@@ -129,4 +569,14 @@ impl DiskState {
 pub enum Message {
     ToggleDisk(usize),
     SelectPartition(String),
+    // Show or hide label, UUID and mount options of a partition, by device
+    ToggleDetails(String),
+    // Mount an unmounted filesystem through UDisks2 and open it, by device
+    Mount(String),
+    Unmount(String),
+    ToggleImages,
+    // Open the partition holding a loop device's backing file with that file highlighted
+    RevealFile(PathBuf),
+    // Check unresponsive mounts again
+    Retry,
 }
\ No newline at end of file
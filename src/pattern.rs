@@ -0,0 +1,70 @@
+// Shell-style glob matching supporting "*" and "?", used for rule files and filters
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            // Remember where the star was so we can backtrack and let it eat one more char
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_patterns_match_only_themselves() {
+        assert!(glob_match("node_modules", "node_modules"));
+        assert!(!glob_match("node_modules", "node_modules2"));
+        assert!(!glob_match("cache", "Cache"));
+    }
+
+    #[test]
+    fn star_matches_any_run_including_none() {
+        assert!(glob_match("*.log", "app.log"));
+        assert!(glob_match("*.log", ".log"));
+        assert!(!glob_match("*.log", "app.log.1"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(glob_match("a*b*c", "abbbc"));
+        assert!(!glob_match("a*b*c", "acb"));
+    }
+
+    #[test]
+    fn question_mark_matches_exactly_one_char() {
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(!glob_match("file?.txt", "file.txt"));
+        assert!(!glob_match("file?.txt", "file10.txt"));
+        assert!(glob_match("?", "é"));
+    }
+
+    #[test]
+    fn detects_wildcards() {
+        assert!(is_glob("*.iso"));
+        assert!(is_glob("img?"));
+        assert!(!is_glob("target"));
+    }
+}
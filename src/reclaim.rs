@@ -0,0 +1,140 @@
+use crate::config::{expand_home, read_lines};
+use crate::pattern::glob_match;
+use std::path::{Path, PathBuf};
+
+// Well-known locations that hold regenerable data. This is purely informational,
+// nothing here is ever deleted by cosmic-dust.
+//
+// Format: <path or directory name> [<marker file in the parent directory>] [= <description>]
+// Entries starting with "/" or "~" match one exact path, anything else matches a
+// directory name anywhere in the tree and may use "*" and "?" globs.
+const DEFAULT_RULES: &str = "\
+~/.cache = User cache
+~/.local/share/Trash = Trash
+.Trash-* = Trash
+target Cargo.toml = Cargo build output
+node_modules package.json = Node.js dependencies
+__pycache__ = Python bytecode cache
+~/.cargo/registry/cache = Cargo download cache
+~/.npm/_cacache = npm cache
+~/.gradle/caches = Gradle cache
+/var/cache/pacman/pkg = Pacman package cache
+/var/cache/apt/archives = APT package cache
+/var/cache/dnf = DNF package cache
+/var/cache/zypp/packages = Zypper package cache
+/var/lib/flatpak/.removed = Unused Flatpak deployments
+/var/lib/flatpak/repo/tmp = Flatpak temporary files
+~/.local/share/flatpak/.removed = Unused Flatpak deployments
+~/.local/share/flatpak/repo/tmp = Flatpak temporary files
+";
+
+// Users can add their own rules in the same format
+const RULES_FILE: &str = "reclaimable.rules";
+
+#[derive(Debug, Clone)]
+enum RuleTarget {
+    Path(PathBuf),
+    Name(String),
+}
+
+#[derive(Debug, Clone)]
+struct ReclaimRule {
+    target: RuleTarget,
+    marker: Option<String>,
+    description: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReclaimRules {
+    rules: Vec<ReclaimRule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReclaimableEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub description: String,
+}
+
+impl ReclaimRules {
+    pub fn load(verbose: bool) -> Self {
+        let user_rules = read_lines(RULES_FILE, verbose);
+        let rules: Vec<ReclaimRule> = DEFAULT_RULES
+            .lines()
+            .map(str::to_string)
+            .chain(user_rules)
+            .filter_map(|line| Self::parse_rule(&line))
+            .collect();
+
+        if verbose {
+            println!("Loaded {} reclaimable space rules", rules.len());
+        }
+
+        ReclaimRules { rules }
+    }
+
+    fn parse_rule(line: &str) -> Option<ReclaimRule> {
+        let (spec, description) = match line.split_once('=') {
+            Some((spec, description)) => (spec, description.trim().to_string()),
+            None => (line, String::new()),
+        };
+
+        let mut fields = spec.split_whitespace();
+        let pattern = fields.next()?;
+        let marker = fields.next().map(str::to_string);
+
+        let target = if pattern.starts_with('/') || pattern.starts_with('~') {
+            RuleTarget::Path(expand_home(pattern))
+        } else {
+            RuleTarget::Name(pattern.to_string())
+        };
+
+        let description = if description.is_empty() {
+            pattern.to_string()
+        } else {
+            description
+        };
+
+        Some(ReclaimRule { target, marker, description })
+    }
+
+    // Returns the description of the first rule matching this directory
+    pub fn matches(&self, path: &Path) -> Option<&str> {
+        let name = path.file_name()?.to_string_lossy();
+
+        self.rules.iter().find_map(|rule| {
+            let matched = match &rule.target {
+                RuleTarget::Path(rule_path) => rule_path == path,
+                RuleTarget::Name(pattern) => glob_match(pattern, &name),
+            };
+            if !matched {
+                return None;
+            }
+
+            // Marker files tell a Cargo "target" apart from any other folder called target
+            let marker_present = match (&rule.marker, path.parent()) {
+                (Some(marker), Some(parent)) => parent.join(marker).exists(),
+                (Some(_), None) => false,
+                (None, _) => true,
+            };
+
+            marker_present.then_some(rule.description.as_str())
+        })
+    }
+}
+
+// Drop entries nested inside another match so their space isn't counted twice
+pub fn remove_nested(mut entries: Vec<ReclaimableEntry>) -> Vec<ReclaimableEntry> {
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut kept: Vec<ReclaimableEntry> = Vec::new();
+    for entry in entries {
+        if kept.last().is_some_and(|parent| entry.path.starts_with(&parent.path)) {
+            continue;
+        }
+        kept.push(entry);
+    }
+
+    kept.sort_by_key(|entry| std::cmp::Reverse(entry.size));
+    kept
+}
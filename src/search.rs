@@ -0,0 +1,115 @@
+use crate::sizes::format_size;
+use crate::tree::ScanTree;
+use cosmic::iced_widget::{button as cosmic_button, scrollable};
+use cosmic::{
+    iced::{Alignment, Length},
+    widget::{column, icon, row, text, text_input},
+    Element,
+};
+use std::path::PathBuf;
+
+// Plenty for browsing, without building thousands of rows for a one letter query
+const RESULT_LIMIT: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchState {
+    pub query: String,
+    // None while the grid is shown, Some once a search has been submitted
+    pub results: Option<Vec<SearchHit>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Query(String),
+    Submit,
+    Select(PathBuf),
+    Close,
+}
+
+impl SearchState {
+    pub fn update(&mut self, message: Message, tree: Option<&ScanTree>) {
+        match message {
+            Message::Query(query) => {
+                if query.trim().is_empty() {
+                    self.results = None;
+                }
+                self.query = query;
+            }
+            Message::Submit => {
+                self.results = tree.map(|tree| {
+                    tree.search(&self.query, RESULT_LIMIT)
+                        .into_iter()
+                        .map(|index| SearchHit {
+                            path: tree.path(index),
+                            size: tree.nodes[index].size,
+                            is_dir: tree.nodes[index].is_dir,
+                        })
+                        .collect()
+                });
+            }
+            Message::Select(_) | Message::Close => self.results = None,
+        }
+    }
+
+    pub fn input_view(&'_ self) -> Element<'_, Message> {
+        text_input("Search partition", &self.query)
+            .on_input(Message::Query)
+            .on_submit(|_| Message::Submit)
+            .width(Length::Fixed(200.0))
+            .into()
+    }
+
+    pub fn results_view(&'_ self) -> Element<'_, Message> {
+        let results = self.results.as_deref().unwrap_or_default();
+
+        let header = row()
+            .push(
+                text(format!("{} matches for \"{}\"", results.len(), self.query.trim()))
+                    .size(16.0)
+                    .width(Length::Fill)
+            )
+            .push(cosmic_button(text("Close")).on_press(Message::Close).padding([4, 8]))
+            .spacing(8)
+            .align_y(Alignment::Center);
+
+        let mut list = column().spacing(4);
+        for hit in results {
+            let name = hit.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let location = hit.path.parent().map(|parent| parent.to_string_lossy().to_string()).unwrap_or_default();
+            list = list.push(
+                cosmic_button(
+                    row()
+                        .push(icon::from_name(if hit.is_dir { "folder" } else { "text-x-generic" }).size(24))
+                        .push(
+                            column()
+                                .spacing(2)
+                                .push(text(name))
+                                .push(text(location).size(12.0))
+                                .width(Length::Fill)
+                        )
+                        .push(text(format_size(hit.size)))
+                        .spacing(8)
+                        .align_y(Alignment::Center)
+                )
+                    .width(Length::Fill)
+                    .padding([4, 8])
+                    .on_press(Message::Select(hit.path.clone()))
+            );
+        }
+
+        column()
+            .push(header)
+            .push(scrollable(list).height(Length::Fill))
+            .spacing(8)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
@@ -9,4 +9,65 @@ pub fn format_size(bytes: u64) -> String {
     }
 
     format!("{:.2} {}", size, UNITS[unit_idx])
-}
\ No newline at end of file
+}
+
+// Parse a human size such as "500", "10M", "1.5 GB" or "2KiB" into bytes
+pub fn parse_size(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let unit = unit.trim().to_ascii_uppercase();
+    let unit = unit.trim_end_matches("IB").trim_end_matches('B');
+    let exponent = match unit {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        "P" => 5,
+        "E" => 6,
+        _ => return None,
+    };
+
+    Some((number * 1024f64.powi(exponent)) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_bytes() {
+        assert_eq!(parse_size("500"), Some(500));
+        assert_eq!(parse_size(" 0 "), Some(0));
+    }
+
+    #[test]
+    fn parses_binary_units_in_any_spelling() {
+        assert_eq!(parse_size("10K"), Some(10 * 1024));
+        assert_eq!(parse_size("10M"), Some(10 * 1024 * 1024));
+        assert_eq!(parse_size("1.5 GB"), Some(1536 * 1024 * 1024));
+        assert_eq!(parse_size("2KiB"), Some(2048));
+        assert_eq!(parse_size("3 tb"), Some(3 * 1024u64.pow(4)));
+        assert_eq!(parse_size("7B"), Some(7));
+    }
+
+    #[test]
+    fn rejects_unknown_units_and_garbage() {
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("M"), None);
+        assert_eq!(parse_size("12 parsecs"), None);
+        assert_eq!(parse_size("1.2.3"), None);
+    }
+
+    #[test]
+    fn reads_sizes_written_the_way_they_are_shown() {
+        for size in [0, 1023, 1536, 5 * 1024 * 1024 * 1024] {
+            assert_eq!(parse_size(&format_size(size)), Some(size));
+        }
+    }
+}
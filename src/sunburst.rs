@@ -0,0 +1,362 @@
+use crate::crawler::FileEntry;
+use crate::files::{entry_for_node, Files};
+use crate::sizes::format_size;
+use crate::tree::ScanTree;
+use crate::ui::Message;
+use cosmic::iced::mouse;
+use cosmic::iced::widget::canvas::{self, event, path, Event, Frame, Geometry, Path, Program, Stroke, Text};
+use cosmic::iced::{alignment, Color, Point, Radians, Rectangle};
+use cosmic::iced_renderer::fallback::Renderer;
+use cosmic::iced_wgpu::Renderer as WgpuRenderer;
+use iced_tiny_skia::Renderer as SkiaRenderer;
+use std::cell::{Cell, Ref, RefCell};
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::sync::{Arc, Weak};
+type CosmicRenderer = Renderer<WgpuRenderer, SkiaRenderer>;
+
+// Rings drawn around the center, one per directory level
+const RINGS: usize = 4;
+
+// Segments thinner than this many radians are left out
+const MIN_SWEEP: f32 = 0.005;
+
+// Share of the radius taken by the disc for the current folder
+const CENTER_SHARE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SunburstSegment {
+    pub node: usize,
+    // 0 is the ring right around the center
+    pub ring: usize,
+    // Radians clockwise from twelve o'clock
+    pub start: f32,
+    pub end: f32,
+}
+
+// Segments for the given nodes spread over the full circle, then their descendants within each
+// parent's arc. Parents come before their children.
+pub fn layout(tree: &ScanTree, nodes: &[usize]) -> Vec<SunburstSegment> {
+    let total: u64 = nodes.iter().map(|&node| tree.nodes[node].size).sum();
+    let mut segments = Vec::new();
+    layout_ring(tree, nodes, total, 0.0, TAU, 0, &mut segments);
+    segments
+}
+
+fn layout_ring(
+    tree: &ScanTree,
+    nodes: &[usize],
+    total: u64,
+    start: f32,
+    sweep: f32,
+    ring: usize,
+    segments: &mut Vec<SunburstSegment>,
+) {
+    if total == 0 || ring >= RINGS {
+        return;
+    }
+    let mut nodes: Vec<usize> = nodes.to_vec();
+    nodes.sort_by_key(|&node| std::cmp::Reverse(tree.nodes[node].size));
+
+    let mut angle = start;
+    for node in nodes {
+        let info = &tree.nodes[node];
+        let node_sweep = sweep * info.size as f32 / total as f32;
+        if node_sweep < MIN_SWEEP {
+            // Sorted largest first, everything after this is thinner still
+            break;
+        }
+        segments.push(SunburstSegment { node, ring, start: angle, end: angle + node_sweep });
+        if info.is_dir {
+            layout_ring(tree, &info.children, info.size, angle, node_sweep, ring + 1, segments);
+        }
+        angle += node_sweep;
+    }
+}
+
+// Polar position of a point relative to the center: distance and clockwise angle from twelve o'clock
+fn polar(x: f32, y: f32) -> (f32, f32) {
+    let angle = (y.atan2(x) + FRAC_PI_2).rem_euclid(TAU);
+    ((x * x + y * y).sqrt(), angle)
+}
+
+// What sits under a point given relative to the center: None outside the chart,
+// Some(None) on the center disc and Some(Some(node)) on a segment
+pub fn hit(segments: &[SunburstSegment], x: f32, y: f32, center_radius: f32, ring_width: f32) -> Option<Option<usize>> {
+    let (distance, angle) = polar(x, y);
+    if distance < center_radius {
+        return Some(None);
+    }
+    let ring = ((distance - center_radius) / ring_width) as usize;
+    if ring >= RINGS {
+        return None;
+    }
+    segments
+        .iter()
+        .find(|segment| segment.ring == ring && angle >= segment.start && angle < segment.end)
+        .map(|segment| Some(segment.node))
+}
+
+// Canvas drawing the folder being browsed as concentric rings, Filelight style. Like the treemap,
+// the first ring follows the filter and everything further out comes from the crawl tree.
+pub struct SunburstView {
+    pub tree: Arc<ScanTree>,
+    pub entries: Vec<(usize, FileEntry)>,
+    pub highlighted: Option<usize>,
+}
+
+// Segments laid out for one tree and set of top-level nodes. The weak reference keeps a dropped
+// tree's address from being reused by the next one while the cache still points at it.
+struct SegmentCache {
+    tree: Weak<ScanTree>,
+    top: Vec<usize>,
+    segments: Vec<SunburstSegment>,
+}
+
+#[derive(Default)]
+pub struct SunburstState {
+    hovered: Option<usize>,
+    // Radius of the whole chart at the last draw, hit testing depends on it
+    radius: Cell<f32>,
+    layout: RefCell<Option<SegmentCache>>,
+}
+
+impl SunburstView {
+    // Laid out again only when the tree or the filtered entries change, not on every mouse move
+    fn segments<'a>(&self, state: &'a SunburstState) -> Ref<'a, [SunburstSegment]> {
+        let top: Vec<usize> = self.entries.iter().map(|(node, _)| *node).collect();
+        let fresh = matches!(
+            &*state.layout.borrow(),
+            Some(cache) if cache.tree.as_ptr() == Arc::as_ptr(&self.tree) && cache.top == top
+        );
+        if !fresh {
+            let segments = layout(&self.tree, &top);
+            *state.layout.borrow_mut() = Some(SegmentCache { tree: Arc::downgrade(&self.tree), top, segments });
+        }
+        Ref::map(state.layout.borrow(), |cache| match cache {
+            Some(cache) => cache.segments.as_slice(),
+            None => &[],
+        })
+    }
+
+    fn geometry(radius: f32) -> (f32, f32) {
+        let center_radius = radius * CENTER_SHARE;
+        (center_radius, (radius - center_radius) / RINGS as f32)
+    }
+}
+
+// Screen angle for a chart angle, canvas arcs start at three o'clock
+fn screen_angle(angle: f32) -> Radians {
+    Radians(angle - FRAC_PI_2)
+}
+
+impl Program<Message, cosmic::Theme, CosmicRenderer> for SunburstView {
+    type State = SunburstState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let (center_radius, ring_width) = Self::geometry(state.radius.get());
+        let under_cursor = cursor.position_in(bounds).and_then(|position| {
+            let center = bounds.size() * 0.5;
+            hit(&self.segments(state), position.x - center.width, position.y - center.height, center_radius, ring_width)
+        });
+
+        match event {
+            Event::Mouse(mouse::Event::CursorMoved { .. }) | Event::Mouse(mouse::Event::CursorLeft) => {
+                let hovered = under_cursor.flatten();
+                if hovered == state.hovered {
+                    return (event::Status::Ignored, None);
+                }
+                state.hovered = hovered;
+                let entry = hovered.map(|node| entry_for_node(&self.tree, &self.entries, node));
+                (event::Status::Ignored, Some(Message::HoverUpdate(entry)))
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => match under_cursor {
+                // The center stands for the current folder, clicking it goes back up
+                Some(None) => (event::Status::Captured, Some(Message::UpButtonClicked)),
+                Some(Some(node)) => {
+                    let folder = if self.tree.nodes[node].is_dir {
+                        Some(node)
+                    } else {
+                        self.tree.nodes[node].parent.filter(|&parent| parent != 0)
+                    };
+                    match folder {
+                        Some(folder) => {
+                            let path = self.tree.path(folder).to_string_lossy().to_string();
+                            (event::Status::Captured, Some(Message::CrawlSubfolder(path)))
+                        }
+                        None => (event::Status::Ignored, None),
+                    }
+                }
+                None => (event::Status::Ignored, None),
+            },
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &CosmicRenderer,
+        theme: &cosmic::Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry<CosmicRenderer>> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let center = frame.center();
+        let radius = bounds.width.min(bounds.height) * 0.5 - 4.0;
+        state.radius.set(radius);
+        let (center_radius, ring_width) = Self::geometry(radius);
+
+        for segment in self.segments(state).iter() {
+            let inner = center_radius + segment.ring as f32 * ring_width;
+            let outer = inner + ring_width;
+            let arc = Path::new(|builder| {
+                builder.arc(path::Arc {
+                    center,
+                    radius: outer,
+                    start_angle: screen_angle(segment.start),
+                    end_angle: screen_angle(segment.end),
+                });
+                builder.arc(path::Arc {
+                    center,
+                    radius: inner,
+                    start_angle: screen_angle(segment.end),
+                    end_angle: screen_angle(segment.start),
+                });
+                builder.close();
+            });
+
+            let info = &self.tree.nodes[segment.node];
+            frame.fill(&arc, Files::color_for_size(info.size));
+            let (border_color, border_width) = if self.highlighted == Some(segment.node) || state.hovered == Some(segment.node) {
+                (Color::WHITE, 2.0)
+            } else {
+                (Color::from_rgba(0.0, 0.0, 0.0, 0.4), 1.0)
+            };
+            frame.stroke(&arc, Stroke::default().with_color(border_color).with_width(border_width));
+
+            // Name along the middle of the segment when the arc is long enough to read it
+            let middle = (inner + outer) * 0.5;
+            let arc_length = (segment.end - segment.start) * middle;
+            if arc_length > 60.0 {
+                let angle = screen_angle((segment.start + segment.end) * 0.5).0;
+                let name = info.name.to_string_lossy();
+                let max_chars = (arc_length.min(ring_width * 2.0) / 7.0) as usize;
+                frame.fill_text(Text {
+                    content: name.chars().take(max_chars).collect(),
+                    position: Point::new(center.x + middle * angle.cos(), center.y + middle * angle.sin()),
+                    color: Color::WHITE,
+                    size: 11.0.into(),
+                    horizontal_alignment: alignment::Horizontal::Center,
+                    vertical_alignment: alignment::Vertical::Center,
+                    ..Text::default()
+                });
+            }
+        }
+
+        // The folder itself in the middle
+        let total: u64 = self.entries.iter().map(|(_, entry)| entry.size).sum();
+        let folder_name = match self.tree.root.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => self.tree.root.display().to_string(),
+        };
+        frame.fill(&Path::circle(center, center_radius), Color::from(theme.cosmic().bg_component_color()));
+        frame.fill_text(Text {
+            content: format!("{}\n{}", folder_name, format_size(total)),
+            position: center,
+            color: Color::from(theme.cosmic().on_bg_component_color()),
+            size: 12.0.into(),
+            horizontal_alignment: alignment::Horizontal::Center,
+            vertical_alignment: alignment::Vertical::Center,
+            ..Text::default()
+        });
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, state: &Self::State, bounds: Rectangle, cursor: mouse::Cursor) -> mouse::Interaction {
+        let (center_radius, ring_width) = Self::geometry(state.radius.get());
+        let over_chart = cursor.position_in(bounds).is_some_and(|position| {
+            let center = bounds.size() * 0.5;
+            hit(&self.segments(state), position.x - center.width, position.y - center.height, center_radius, ring_width).is_some()
+        });
+        if over_chart {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+pub fn sunburst_canvas<'a>(view: SunburstView) -> cosmic::iced_core::Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+    canvas::Canvas::new(view)
+        .width(cosmic::iced::Length::Fill)
+        .height(cosmic::iced::Length::Fill)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::fixture::{assert_close, tree};
+    use std::path::PathBuf;
+
+    #[test]
+    fn splits_the_circle_by_size_and_nests_children_in_their_parent() {
+        let tree = tree();
+        let segments = layout(&tree, &tree.nodes[0].children);
+        let expected = [
+            (1, 0, 0.0, 0.75 * TAU),
+            (2, 1, 0.0, 0.5625 * TAU),
+            (3, 1, 0.5625 * TAU, 0.75 * TAU),
+            (4, 0, 0.75 * TAU, TAU),
+        ];
+        assert_eq!(segments.len(), expected.len());
+        for (segment, (node, ring, start, end)) in segments.iter().zip(expected) {
+            assert_eq!((segment.node, segment.ring), (node, ring));
+            assert_close(segment.start, start);
+            assert_close(segment.end, end);
+        }
+    }
+
+    #[test]
+    fn leaves_out_slivers_and_rings_past_the_last() {
+        let mut tree = ScanTree::new(PathBuf::from("/deep"));
+        let mut parent = 0;
+        for depth in 0..RINGS + 2 {
+            parent = tree.push(parent, format!("level{}", depth).into(), 0, true, None);
+        }
+        tree.push(parent, "bottom.bin".into(), 1_000_000, false, None);
+        tree.push(0, "tiny".into(), 1, false, None);
+        tree.finalize_sizes();
+
+        let segments = layout(&tree, &tree.nodes[0].children);
+        assert_eq!(segments.len(), RINGS);
+        assert!(segments.iter().enumerate().all(|(ring, segment)| segment.ring == ring));
+        assert!(segments.iter().all(|segment| tree.nodes[segment.node].name != "tiny"));
+    }
+
+    #[test]
+    fn hit_maps_points_to_rings_and_clockwise_angles() {
+        let tree = tree();
+        let segments = layout(&tree, &tree.nodes[0].children);
+        let hit = |x, y| hit(&segments, x, y, 20.0, 10.0);
+
+        // Center disc, then twelve, three and just past nine o'clock on the first ring
+        assert_eq!(hit(0.0, 5.0), Some(None));
+        assert_eq!(hit(0.0, -25.0), Some(Some(1)));
+        assert_eq!(hit(25.0, 0.0), Some(Some(1)));
+        assert_eq!(hit(-25.0, -1.0), Some(Some(4)));
+        // Second ring holds big/'s files, nothing is stacked on the plain file
+        assert_eq!(hit(0.0, -35.0), Some(Some(2)));
+        assert_eq!(hit(-35.0, 5.0), Some(Some(3)));
+        assert_eq!(hit(-35.0, -1.0), None);
+        // Past the outermost ring
+        assert_eq!(hit(0.0, 70.0), None);
+    }
+}
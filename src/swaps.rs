@@ -0,0 +1,93 @@
+use crate::mountinfo::unescape;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapKind {
+    Partition,
+    File,
+}
+
+// One active swap area from /proc/swaps
+#[derive(Debug, Clone)]
+pub struct SwapArea {
+    pub path: PathBuf,
+    pub kind: SwapKind,
+    pub size: u64,
+    pub used: u64,
+    pub priority: i32,
+}
+
+// Filename Type Size Used Priority, sizes in KiB and paths escaped like in mountinfo
+// /dev/nvme0n1p3 partition 16777212 1024 -2
+pub fn parse_swaps(contents: &str) -> Vec<SwapArea> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            let kind = match fields[1] {
+                "partition" => SwapKind::Partition,
+                "file" => SwapKind::File,
+                _ => return None,
+            };
+            Some(SwapArea {
+                path: PathBuf::from(unescape(fields[0])),
+                kind,
+                size: fields[2].parse::<u64>().ok()? * 1024,
+                used: fields[3].parse::<u64>().ok()? * 1024,
+                priority: fields[4].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+pub fn read_swaps() -> Vec<SwapArea> {
+    fs::read_to_string("/proc/swaps")
+        .map(|contents| parse_swaps(&contents))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_partitions_and_files_in_bytes() {
+        let swaps = parse_swaps(
+            "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n\
+             /dev/dm-1                               partition\t8388604\t\t1024\t\t-2\n\
+             /swapfile                               file\t\t2097148\t\t0\t\t10\n"
+        );
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].path, PathBuf::from("/dev/dm-1"));
+        assert_eq!(swaps[0].kind, SwapKind::Partition);
+        assert_eq!(swaps[0].size, 8388604 * 1024);
+        assert_eq!(swaps[0].used, 1024 * 1024);
+        assert_eq!(swaps[0].priority, -2);
+        assert_eq!(swaps[1].path, PathBuf::from("/swapfile"));
+        assert_eq!(swaps[1].kind, SwapKind::File);
+        assert_eq!(swaps[1].priority, 10);
+    }
+
+    #[test]
+    fn decodes_escaped_paths() {
+        let swaps = parse_swaps("Filename Type Size Used Priority\n/mnt/spare\\040disk/swap file 1024 0 -3\n");
+        assert_eq!(swaps[0].path, PathBuf::from("/mnt/spare disk/swap"));
+    }
+
+    #[test]
+    fn skips_the_header_and_malformed_lines() {
+        let swaps = parse_swaps(
+            "Filename Type Size Used Priority\n\
+             /dev/sda1 volume 1024 0 -1\n\
+             /dev/sda2 partition 1024\n\
+             /dev/sda3 partition lots 0 -2\n"
+        );
+        assert!(swaps.is_empty());
+        assert!(parse_swaps("").is_empty());
+    }
+}
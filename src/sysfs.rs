@@ -0,0 +1,298 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Everything here takes the sysfs mount point so it can be pointed at a fixture tree
+pub const SYSFS_ROOT: &str = "/sys";
+
+// Only partitions carry a "partition" attribute (their index on the disk)
+pub fn is_partition(sysfs: &Path, name: &str) -> bool {
+    sysfs.join("class/block").join(name).join("partition").exists()
+}
+
+// Kernel name of the whole disk a block device belongs to. Partitions are nested in their
+// disk's directory (.../block/mmcblk0/mmcblk0p1), anything else is a disk of its own.
+pub fn parent_disk(sysfs: &Path, name: &str) -> Option<String> {
+    if !is_partition(sysfs, name) {
+        return Some(name.to_string());
+    }
+
+    let resolved = fs::canonicalize(sysfs.join("class/block").join(name)).ok()?;
+    resolved
+        .parent()?
+        .file_name()
+        .map(|disk| disk.to_string_lossy().to_string())
+}
+
+fn read_attribute(sysfs: &Path, name: &str, attribute: &str) -> Option<String> {
+    fs::read_to_string(sysfs.join("class/block").join(name).join(attribute))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn list_dir(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+// Devices built on top of this one (dm-crypt, LVM volumes, md arrays)
+pub fn holders(sysfs: &Path, name: &str) -> Vec<String> {
+    list_dir(&sysfs.join("class/block").join(name).join("holders"))
+}
+
+// Devices this one is built from, empty for plain disks and partitions
+pub fn slaves(sysfs: &Path, name: &str) -> Vec<String> {
+    list_dir(&sysfs.join("class/block").join(name).join("slaves"))
+}
+
+pub fn partitions(sysfs: &Path, disk: &str) -> Vec<String> {
+    list_dir(&sysfs.join("class/block").join(disk))
+        .into_iter()
+        .filter(|child| is_partition(sysfs, child) && parent_disk(sysfs, child).as_deref() == Some(disk))
+        .collect()
+}
+
+pub fn dm_name(sysfs: &Path, name: &str) -> Option<String> {
+    read_attribute(sysfs, name, "dm/name")
+}
+
+// Prefixed by the owning subsystem, e.g. "CRYPT-LUKS2-..." or "LVM-..."
+pub fn dm_uuid(sysfs: &Path, name: &str) -> Option<String> {
+    read_attribute(sysfs, name, "dm/uuid")
+}
+
+pub fn md_level(sysfs: &Path, name: &str) -> Option<String> {
+    read_attribute(sysfs, name, "md/level")
+}
+
+// Sizes and offsets in sysfs are always counted in 512-byte sectors, whatever the hardware uses
+const SECTOR_SIZE: u64 = 512;
+
+pub fn size_bytes(sysfs: &Path, name: &str) -> Option<u64> {
+    read_attribute(sysfs, name, "size")?.parse::<u64>().ok().map(|sectors| sectors * SECTOR_SIZE)
+}
+
+// Where a partition begins on its disk
+pub fn start_bytes(sysfs: &Path, name: &str) -> Option<u64> {
+    read_attribute(sysfs, name, "start")?.parse::<u64>().ok().map(|sectors| sectors * SECTOR_SIZE)
+}
+
+// File a loop device is attached to, e.g. /var/lib/snapd/snaps/core22_1380.snap
+pub fn loop_backing_file(sysfs: &Path, name: &str) -> Option<PathBuf> {
+    read_attribute(sysfs, name, "loop/backing_file").map(PathBuf::from)
+}
+
+// A throwaway sysfs with devices under devices/ and class/block links to them, like the real one.
+// Shared by every module that reads sysfs in its tests.
+#[cfg(test)]
+pub mod fixture {
+    use super::SECTOR_SIZE;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use std::path::{Path, PathBuf};
+
+    pub struct Fixture {
+        pub root: PathBuf,
+    }
+
+    impl Fixture {
+        pub fn new(name: &str) -> Fixture {
+            let root = std::env::temp_dir().join(format!("cosmic-dust-sysfs-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(root.join("class/block")).unwrap();
+            Fixture { root }
+        }
+
+        // Device directory relative to devices/, e.g. "pci0000:00/nvme/block/nvme0n1/nvme0n1p2"
+        pub fn device(&self, relative: &str) -> &Fixture {
+            let dir = self.root.join("devices").join(relative);
+            fs::create_dir_all(&dir).unwrap();
+            let name = dir.file_name().unwrap();
+            symlink(Path::new("../../devices").join(relative), self.root.join("class/block").join(name)).unwrap();
+            self
+        }
+
+        pub fn partition(&self, relative: &str, index: u32) -> &Fixture {
+            self.device(relative);
+            fs::write(self.root.join("devices").join(relative).join("partition"), format!("{}\n", index)).unwrap();
+            self
+        }
+
+        // holders/ on the lower device and slaves/ on the upper one
+        pub fn stack(&self, lower: &str, upper: &str) -> &Fixture {
+            let block = self.root.join("class/block");
+            fs::create_dir_all(block.join(lower).join("holders")).unwrap();
+            fs::create_dir_all(block.join(upper).join("slaves")).unwrap();
+            fs::write(block.join(lower).join("holders").join(upper), "").unwrap();
+            fs::write(block.join(upper).join("slaves").join(lower), "").unwrap();
+            self
+        }
+
+        // Attribute file of a device added earlier, e.g. ("dm-0", "dm/name", "luks-1234")
+        pub fn attribute(&self, name: &str, attribute: &str, value: &str) -> &Fixture {
+            let file = self.root.join("class/block").join(name).join(attribute);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, format!("{}\n", value)).unwrap();
+            self
+        }
+
+        pub fn size(&self, name: &str, bytes: u64) -> &Fixture {
+            self.attribute(name, "size", &(bytes / SECTOR_SIZE).to_string())
+        }
+
+        pub fn start(&self, name: &str, bytes: u64) -> &Fixture {
+            self.attribute(name, "start", &(bytes / SECTOR_SIZE).to_string())
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixture::Fixture;
+    use super::*;
+
+    #[test]
+    fn mmc_partition_belongs_to_its_card() {
+        let sysfs = Fixture::new("mmc");
+        sysfs
+            .device("platform/mmc/block/mmcblk0")
+            .partition("platform/mmc/block/mmcblk0/mmcblk0p1", 1);
+
+        assert!(is_partition(&sysfs.root, "mmcblk0p1"));
+        assert!(!is_partition(&sysfs.root, "mmcblk0"));
+        assert_eq!(parent_disk(&sysfs.root, "mmcblk0p1").as_deref(), Some("mmcblk0"));
+        assert_eq!(partitions(&sysfs.root, "mmcblk0"), ["mmcblk0p1"]);
+    }
+
+    #[test]
+    fn nvme_disk_ending_in_a_digit_is_a_whole_disk() {
+        let sysfs = Fixture::new("nvme");
+        sysfs
+            .device("pci0000:00/nvme/block/nvme0n1")
+            .partition("pci0000:00/nvme/block/nvme0n1/nvme0n1p1", 1)
+            .partition("pci0000:00/nvme/block/nvme0n1/nvme0n1p2", 2);
+
+        assert!(!is_partition(&sysfs.root, "nvme0n1"));
+        assert_eq!(parent_disk(&sysfs.root, "nvme0n1").as_deref(), Some("nvme0n1"));
+        assert_eq!(parent_disk(&sysfs.root, "nvme0n1p2").as_deref(), Some("nvme0n1"));
+        assert_eq!(partitions(&sysfs.root, "nvme0n1"), ["nvme0n1p1", "nvme0n1p2"]);
+        assert!(partitions(&sysfs.root, "nvme0n1p2").is_empty());
+    }
+
+    #[test]
+    fn partitioned_loop_device() {
+        let sysfs = Fixture::new("loop");
+        sysfs
+            .device("virtual/block/loop0")
+            .partition("virtual/block/loop0/loop0p1", 1);
+
+        assert!(is_partition(&sysfs.root, "loop0p1"));
+        assert_eq!(parent_disk(&sysfs.root, "loop0p1").as_deref(), Some("loop0"));
+        assert_eq!(partitions(&sysfs.root, "loop0"), ["loop0p1"]);
+    }
+
+    #[test]
+    fn md_array_is_a_whole_disk() {
+        let sysfs = Fixture::new("md");
+        sysfs
+            .device("pci0000:00/ata1/block/sda")
+            .partition("pci0000:00/ata1/block/sda/sda1", 1)
+            .device("pci0000:00/ata2/block/sdb")
+            .partition("pci0000:00/ata2/block/sdb/sdb1", 1)
+            .device("virtual/block/md127")
+            .stack("sda1", "md127")
+            .stack("sdb1", "md127");
+
+        assert!(!is_partition(&sysfs.root, "md127"));
+        assert_eq!(parent_disk(&sysfs.root, "md127").as_deref(), Some("md127"));
+        assert!(partitions(&sysfs.root, "md127").is_empty());
+        assert_eq!(parent_disk(&sysfs.root, "sda1").as_deref(), Some("sda"));
+    }
+
+    #[test]
+    fn device_mapper_volume_is_a_whole_disk() {
+        let sysfs = Fixture::new("dm");
+        sysfs
+            .device("pci0000:00/nvme/block/nvme0n1")
+            .partition("pci0000:00/nvme/block/nvme0n1/nvme0n1p2", 2)
+            .device("virtual/block/dm-0")
+            .stack("nvme0n1p2", "dm-0");
+
+        assert!(!is_partition(&sysfs.root, "dm-0"));
+        assert_eq!(parent_disk(&sysfs.root, "dm-0").as_deref(), Some("dm-0"));
+        assert!(partitions(&sysfs.root, "dm-0").is_empty());
+        assert_eq!(parent_disk(&sysfs.root, "nvme0n1p2").as_deref(), Some("nvme0n1"));
+    }
+
+    #[test]
+    fn md_array_is_held_by_its_members() {
+        let sysfs = Fixture::new("md-stack");
+        sysfs
+            .device("pci0000:00/ata1/block/sda")
+            .partition("pci0000:00/ata1/block/sda/sda1", 1)
+            .device("pci0000:00/ata2/block/sdb")
+            .partition("pci0000:00/ata2/block/sdb/sdb1", 1)
+            .device("virtual/block/md127")
+            .stack("sda1", "md127")
+            .stack("sdb1", "md127");
+        sysfs.attribute("md127", "md/level", "raid1");
+
+        assert_eq!(slaves(&sysfs.root, "md127"), ["sda1", "sdb1"]);
+        assert_eq!(holders(&sysfs.root, "sda1"), ["md127"]);
+        assert_eq!(holders(&sysfs.root, "sdb1"), ["md127"]);
+        assert!(holders(&sysfs.root, "md127").is_empty());
+        assert_eq!(md_level(&sysfs.root, "md127").as_deref(), Some("raid1"));
+        assert_eq!(md_level(&sysfs.root, "sda1"), None);
+    }
+
+    #[test]
+    fn device_mapper_volume_lists_what_it_sits_on() {
+        let sysfs = Fixture::new("dm-stack");
+        sysfs
+            .device("pci0000:00/nvme/block/nvme0n1")
+            .partition("pci0000:00/nvme/block/nvme0n1/nvme0n1p2", 2)
+            .device("virtual/block/dm-0")
+            .device("virtual/block/dm-1")
+            .stack("nvme0n1p2", "dm-0")
+            .stack("dm-0", "dm-1");
+        sysfs
+            .attribute("dm-0", "dm/name", "luks-1234")
+            .attribute("dm-0", "dm/uuid", "CRYPT-LUKS2-1234-luks-1234");
+
+        assert_eq!(slaves(&sysfs.root, "dm-0"), ["nvme0n1p2"]);
+        assert_eq!(holders(&sysfs.root, "nvme0n1p2"), ["dm-0"]);
+        assert_eq!(holders(&sysfs.root, "dm-0"), ["dm-1"]);
+        assert_eq!(slaves(&sysfs.root, "dm-1"), ["dm-0"]);
+        assert!(slaves(&sysfs.root, "nvme0n1p2").is_empty());
+        assert_eq!(dm_name(&sysfs.root, "dm-0").as_deref(), Some("luks-1234"));
+        assert!(dm_uuid(&sysfs.root, "dm-0").unwrap().starts_with("CRYPT-"));
+        assert_eq!(dm_name(&sysfs.root, "dm-1"), None);
+    }
+
+    #[test]
+    fn loop_device_names_its_backing_file() {
+        let sysfs = Fixture::new("loop-backing");
+        sysfs
+            .device("virtual/block/loop0")
+            .device("virtual/block/loop1");
+        sysfs.attribute("loop0", "loop/backing_file", "/var/lib/snapd/snaps/core22_1380.snap");
+
+        assert_eq!(loop_backing_file(&sysfs.root, "loop0"), Some(PathBuf::from("/var/lib/snapd/snaps/core22_1380.snap")));
+        // Detached loop devices have no loop/ directory at all
+        assert_eq!(loop_backing_file(&sysfs.root, "loop1"), None);
+    }
+}
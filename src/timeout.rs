@@ -0,0 +1,75 @@
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long a filesystem call may take before its mount is considered hung
+pub const IO_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Keys of calls started with spawn_once that haven't returned yet
+static IN_FLIGHT: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Result of a call running on its own thread. Calls into a dead network mount can block
+// forever, so the thread is left behind if it doesn't finish in time.
+pub struct Pending<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T: Send + 'static> Pending<T> {
+    pub fn spawn(f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(f());
+        });
+        Pending { receiver }
+    }
+
+    // Like spawn, but None while an earlier call with the same key is still stuck, so rescanning
+    // a hung mount doesn't leave one more blocked thread behind each time
+    pub fn spawn_once(key: String, f: impl FnOnce() -> T + Send + 'static) -> Option<Self> {
+        {
+            let mut in_flight = IN_FLIGHT.lock().unwrap_or_else(PoisonError::into_inner);
+            if in_flight.contains(&key) {
+                return None;
+            }
+            in_flight.push(key.clone());
+        }
+        Some(Self::spawn(move || {
+            let result = f();
+            IN_FLIGHT
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .retain(|running| *running != key);
+            result
+        }))
+    }
+
+    // None if the call is still stuck at the deadline
+    pub fn wait_until(self, deadline: Instant) -> Option<T> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        self.receiver.recv_timeout(remaining).ok()
+    }
+}
+
+// None if the call doesn't finish in time or the last one with this key never did
+pub fn run_with_timeout<T: Send + 'static>(key: String, f: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    Pending::spawn_once(key, f)?.wait_until(Instant::now() + IO_TIMEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_a_key_until_its_call_returns() {
+        let (release, blocked) = mpsc::channel::<()>();
+        let stuck = Pending::spawn_once("stuck".to_string(), move || blocked.recv().is_ok())
+            .expect("nothing in flight yet");
+        assert!(Pending::spawn_once("stuck".to_string(), || true).is_none());
+        assert_eq!(run_with_timeout("other".to_string(), || 7), Some(7));
+
+        release.send(()).unwrap();
+        assert_eq!(stuck.wait_until(Instant::now() + IO_TIMEOUT), Some(true));
+        assert_eq!(run_with_timeout("stuck".to_string(), || 8), Some(8));
+    }
+}
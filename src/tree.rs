@@ -0,0 +1,165 @@
+use crate::pattern::{glob_match, is_glob};
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    pub name: OsString,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub size: u64,
+    pub is_dir: bool,
+    pub modified: Option<SystemTime>,
+    // Files and folders anywhere below this one, counted by finalize_sizes
+    pub items: usize,
+}
+
+// Every path found by a crawl. Node 0 is the crawl root and children always come after their parent.
+#[derive(Debug, Clone)]
+pub struct ScanTree {
+    pub root: PathBuf,
+    pub nodes: Vec<TreeNode>,
+}
+
+impl ScanTree {
+    pub fn new(root: PathBuf) -> Self {
+        let root_node = TreeNode {
+            name: root.as_os_str().to_os_string(),
+            parent: None,
+            children: Vec::new(),
+            size: 0,
+            is_dir: true,
+            modified: None,
+            items: 0,
+        };
+        ScanTree { root, nodes: vec![root_node] }
+    }
+
+    pub fn push(&mut self, parent: usize, name: OsString, size: u64, is_dir: bool, modified: Option<SystemTime>) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(TreeNode {
+            name,
+            parent: Some(parent),
+            children: Vec::new(),
+            size,
+            is_dir,
+            modified,
+            items: 0,
+        });
+        self.nodes[parent].children.push(index);
+        index
+    }
+
+    // Roll file sizes and item counts up into their directories, children are visited before parents
+    pub fn finalize_sizes(&mut self) {
+        for index in (1..self.nodes.len()).rev() {
+            if let Some(parent) = self.nodes[index].parent {
+                self.nodes[parent].size += self.nodes[index].size;
+                self.nodes[parent].items += self.nodes[index].items + 1;
+            }
+        }
+    }
+
+    pub fn path(&self, index: usize) -> PathBuf {
+        let mut names = Vec::new();
+        let mut current = index;
+        while let Some(parent) = self.nodes[current].parent {
+            names.push(&self.nodes[current].name);
+            current = parent;
+        }
+
+        let mut path = self.root.clone();
+        for name in names.into_iter().rev() {
+            path.push(name);
+        }
+        path
+    }
+
+    pub fn find(&self, path: &Path) -> Option<usize> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let mut current = 0;
+        for component in relative.components() {
+            let Component::Normal(name) = component else {
+                return None;
+            };
+            current = *self.nodes[current]
+                .children
+                .iter()
+                .find(|&&child| self.nodes[child].name == name)?;
+        }
+        Some(current)
+    }
+
+    // Case-insensitive substring or glob search over every name, largest matches first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<usize> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let glob = is_glob(&query);
+
+        let mut matches: Vec<usize> = (1..self.nodes.len())
+            .filter(|&index| {
+                let name = self.nodes[index].name.to_string_lossy().to_lowercase();
+                if glob {
+                    glob_match(&query, &name)
+                } else {
+                    name.contains(&query)
+                }
+            })
+            .collect();
+
+        matches.sort_by_key(|&index| std::cmp::Reverse(self.nodes[index].size));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+// Tree and float comparison shared by the tests of the views drawn from a tree
+#[cfg(test)]
+pub mod fixture {
+    use super::ScanTree;
+    use std::path::PathBuf;
+
+    // /data holding big/ (a 450 and a 150 byte file) and a 200 byte file. Nodes are numbered in
+    // that order: 1 big/, 2 movie.mkv, 3 notes.txt, 4 photo.jpg.
+    pub fn tree() -> ScanTree {
+        let mut tree = ScanTree::new(PathBuf::from("/data"));
+        let big = tree.push(0, "big".into(), 0, true, None);
+        tree.push(big, "movie.mkv".into(), 450, false, None);
+        tree.push(big, "notes.txt".into(), 150, false, None);
+        tree.push(0, "photo.jpg".into(), 200, false, None);
+        tree.finalize_sizes();
+        tree
+    }
+
+    // Layouts are computed in f32, so compare to a few digits of the expected value
+    pub fn assert_close(actual: f32, expected: f32) {
+        let tolerance = 1e-4 * expected.abs().max(1.0);
+        assert!((actual - expected).abs() < tolerance, "{} != {}", actual, expected);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_sizes_and_item_counts_up_to_every_parent() {
+        let mut tree = ScanTree::new(PathBuf::from("/home"));
+        let docs = tree.push(0, "docs".into(), 0, true, None);
+        let empty = tree.push(docs, "empty".into(), 0, true, None);
+        let report = tree.push(docs, "report.pdf".into(), 300, false, None);
+        let photo = tree.push(0, "photo.jpg".into(), 200, false, None);
+        tree.finalize_sizes();
+
+        assert_eq!((tree.nodes[0].size, tree.nodes[0].items), (500, 4));
+        assert_eq!((tree.nodes[docs].size, tree.nodes[docs].items), (300, 2));
+        assert_eq!((tree.nodes[empty].size, tree.nodes[empty].items), (0, 0));
+        assert_eq!((tree.nodes[report].size, tree.nodes[report].items), (300, 0));
+        assert_eq!(tree.nodes[photo].items, 0);
+        assert_eq!(tree.find(Path::new("/home/docs/report.pdf")), Some(report));
+        assert_eq!(tree.path(empty), PathBuf::from("/home/docs/empty"));
+    }
+}
@@ -0,0 +1,369 @@
+use crate::crawler::FileEntry;
+use crate::files::{entry_for_node, Files};
+use crate::sizes::format_size;
+use crate::tree::ScanTree;
+use crate::ui::Message;
+use cosmic::iced::mouse;
+use cosmic::iced::widget::canvas::{self, event, Event, Frame, Geometry, Path, Program, Stroke, Text};
+use cosmic::iced::{Color, Point, Rectangle, Size};
+use cosmic::iced_renderer::fallback::Renderer;
+use cosmic::iced_wgpu::Renderer as WgpuRenderer;
+use iced_tiny_skia::Renderer as SkiaRenderer;
+use std::cell::RefCell;
+use std::sync::Arc;
+type CosmicRenderer = Renderer<WgpuRenderer, SkiaRenderer>;
+
+// Room kept around a directory's children, the top strip holds its name
+const PADDING: f32 = 2.0;
+const HEADER_HEIGHT: f32 = 16.0;
+
+// Directories are only opened up this many levels below the one being viewed
+const MAX_DEPTH: usize = 3;
+
+// Tiles narrower or shorter than this are not worth drawing or nesting into
+const MIN_SIDE: f32 = 3.0;
+
+// How much darker each nested level is drawn than the one around it
+const DEPTH_SHADE: f32 = 0.12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TreemapTile {
+    pub node: usize,
+    pub rect: Rect,
+    // 0 for the entries of the folder being viewed
+    pub depth: usize,
+}
+
+// Aspect ratio of the worst tile in a row of the given areas laid along a side of this length
+fn worst_ratio(areas: &[f32], side: f32) -> f32 {
+    let sum: f32 = areas.iter().sum();
+    let largest = areas.iter().cloned().fold(0.0, f32::max);
+    let smallest = areas.iter().cloned().fold(f32::INFINITY, f32::min);
+    let side_squared = side * side;
+    (side_squared * largest / (sum * sum)).max(sum * sum / (side_squared * smallest))
+}
+
+// Squarified treemap (Bruls, Huizing, van Wijk). Sizes must be sorted largest first, every
+// returned rectangle has an area proportional to its size.
+pub fn squarify(sizes: &[u64], bounds: Rect) -> Vec<Rect> {
+    let total: u64 = sizes.iter().sum();
+    if total == 0 || bounds.width <= 0.0 || bounds.height <= 0.0 {
+        return vec![Rect { width: 0.0, height: 0.0, ..bounds }; sizes.len()];
+    }
+
+    let scale = bounds.width * bounds.height / total as f32;
+    let areas: Vec<f32> = sizes.iter().map(|&size| size as f32 * scale).collect();
+    let mut rects = Vec::with_capacity(sizes.len());
+    let mut free = bounds;
+    let mut start = 0;
+
+    while start < areas.len() {
+        // Grow the row as long as that doesn't make its worst tile any less square
+        let side = free.width.min(free.height);
+        let mut end = start + 1;
+        while end < areas.len()
+            && areas[end] > 0.0
+            && worst_ratio(&areas[start..=end], side) <= worst_ratio(&areas[start..end], side)
+        {
+            end += 1;
+        }
+
+        let row_area: f32 = areas[start..end].iter().sum();
+        if free.width >= free.height {
+            // Column along the left edge
+            let column_width = if free.height > 0.0 { row_area / free.height } else { 0.0 };
+            let mut y = free.y;
+            for &area in &areas[start..end] {
+                let height = if column_width > 0.0 { area / column_width } else { 0.0 };
+                rects.push(Rect { x: free.x, y, width: column_width, height });
+                y += height;
+            }
+            free.x += column_width;
+            free.width = (free.width - column_width).max(0.0);
+        } else {
+            // Row along the top edge
+            let row_height = if free.width > 0.0 { row_area / free.width } else { 0.0 };
+            let mut x = free.x;
+            for &area in &areas[start..end] {
+                let width = if row_height > 0.0 { area / row_height } else { 0.0 };
+                rects.push(Rect { x, y: free.y, width, height: row_height });
+                x += width;
+            }
+            free.y += row_height;
+            free.height = (free.height - row_height).max(0.0);
+        }
+        start = end;
+    }
+    rects
+}
+
+// Tiles for the given nodes and, where there's room, their contents. Parents come before their
+// children, so drawing in order paints nested levels on top.
+pub fn layout(tree: &ScanTree, nodes: &[usize], bounds: Rect) -> Vec<TreemapTile> {
+    let mut tiles = Vec::new();
+    layout_level(tree, nodes, bounds, 0, &mut tiles);
+    tiles
+}
+
+fn layout_level(tree: &ScanTree, nodes: &[usize], bounds: Rect, depth: usize, tiles: &mut Vec<TreemapTile>) {
+    let mut nodes: Vec<usize> = nodes.iter().copied().filter(|&node| tree.nodes[node].size > 0).collect();
+    nodes.sort_by_key(|&node| std::cmp::Reverse(tree.nodes[node].size));
+    let sizes: Vec<u64> = nodes.iter().map(|&node| tree.nodes[node].size).collect();
+
+    for (node, rect) in nodes.into_iter().zip(squarify(&sizes, bounds)) {
+        if rect.width < MIN_SIDE || rect.height < MIN_SIDE {
+            continue;
+        }
+        tiles.push(TreemapTile { node, rect, depth });
+
+        let inner = Rect {
+            x: rect.x + PADDING,
+            y: rect.y + HEADER_HEIGHT,
+            width: rect.width - 2.0 * PADDING,
+            height: rect.height - HEADER_HEIGHT - PADDING,
+        };
+        let node_info = &tree.nodes[node];
+        if node_info.is_dir && depth + 1 < MAX_DEPTH && inner.width >= MIN_SIDE * 4.0 && inner.height >= MIN_SIDE * 4.0 {
+            layout_level(tree, &node_info.children, inner, depth + 1, tiles);
+        }
+    }
+}
+
+// Deepest tile under a point
+pub fn tile_at(tiles: &[TreemapTile], x: f32, y: f32) -> Option<&TreemapTile> {
+    tiles.iter().rev().find(|tile| tile.rect.contains(x, y))
+}
+
+// Canvas drawing the folder being browsed as a treemap. Top-level tiles come from the filtered
+// entries, anything nested below them is taken straight from the crawl tree.
+pub struct TreemapView {
+    pub tree: Arc<ScanTree>,
+    pub entries: Vec<(usize, FileEntry)>,
+    pub highlighted: Option<usize>,
+}
+
+#[derive(Default)]
+pub struct TreemapState {
+    // Filled in by draw, used to find what's under the cursor
+    tiles: RefCell<Vec<TreemapTile>>,
+    hovered: Option<usize>,
+}
+
+impl Program<Message, cosmic::Theme, CosmicRenderer> for TreemapView {
+    type State = TreemapState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let under_cursor = cursor.position_in(bounds).and_then(|position| {
+            tile_at(&state.tiles.borrow(), position.x, position.y).map(|tile| tile.node)
+        });
+
+        match event {
+            Event::Mouse(mouse::Event::CursorMoved { .. }) | Event::Mouse(mouse::Event::CursorLeft) => {
+                if under_cursor == state.hovered {
+                    return (event::Status::Ignored, None);
+                }
+                state.hovered = under_cursor;
+                let entry = under_cursor.map(|node| entry_for_node(&self.tree, &self.entries, node));
+                (event::Status::Ignored, Some(Message::HoverUpdate(entry)))
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                // Zoom into the clicked folder, or the folder holding the clicked file
+                let folder = under_cursor.and_then(|node| {
+                    if self.tree.nodes[node].is_dir {
+                        Some(node)
+                    } else {
+                        self.tree.nodes[node].parent.filter(|&parent| parent != 0)
+                    }
+                });
+                match folder {
+                    Some(folder) => {
+                        let path = self.tree.path(folder).to_string_lossy().to_string();
+                        (event::Status::Captured, Some(Message::CrawlSubfolder(path)))
+                    }
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &CosmicRenderer,
+        _theme: &cosmic::Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry<CosmicRenderer>> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let top: Vec<usize> = self.entries.iter().map(|(node, _)| *node).collect();
+        let tiles = layout(&self.tree, &top, Rect { x: 0.0, y: 0.0, width: bounds.width, height: bounds.height });
+
+        for tile in &tiles {
+            let info = &self.tree.nodes[tile.node];
+            let top_left = Point::new(tile.rect.x, tile.rect.y);
+            let size = Size::new(tile.rect.width, tile.rect.height);
+            let path = Path::rectangle(top_left, size);
+
+            let color = Files::color_for_size(info.size);
+            let shade = 1.0 - DEPTH_SHADE * tile.depth as f32;
+            frame.fill(&path, Color { r: color.r * shade, g: color.g * shade, b: color.b * shade, ..color });
+            let (border_color, border_width) = if self.highlighted == Some(tile.node) || state.hovered == Some(tile.node) {
+                (Color::WHITE, 2.0)
+            } else {
+                (Color::from_rgba(0.0, 0.0, 0.0, 0.4), 1.0)
+            };
+            frame.stroke(&path, Stroke::default().with_color(border_color).with_width(border_width));
+
+            // Name strip, only where it fits
+            if tile.rect.width > 40.0 && tile.rect.height > HEADER_HEIGHT {
+                let name = info.name.to_string_lossy();
+                let max_chars = ((tile.rect.width - 6.0) / 7.0) as usize;
+                let label = if tile.rect.height > HEADER_HEIGHT * 2.0 && !info.is_dir {
+                    format!("{} ({})", name, format_size(info.size))
+                } else {
+                    name.to_string()
+                };
+                frame.fill_text(Text {
+                    content: label.chars().take(max_chars).collect(),
+                    position: Point::new(tile.rect.x + 3.0, tile.rect.y + 2.0),
+                    color: Color::WHITE,
+                    size: 12.0.into(),
+                    ..Text::default()
+                });
+            }
+        }
+
+        *state.tiles.borrow_mut() = tiles;
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, state: &Self::State, bounds: Rectangle, cursor: mouse::Cursor) -> mouse::Interaction {
+        let over_tile = cursor
+            .position_in(bounds)
+            .is_some_and(|position| tile_at(&state.tiles.borrow(), position.x, position.y).is_some());
+        if over_tile {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+pub fn treemap_canvas<'a>(view: TreemapView) -> cosmic::iced_core::Element<'a, Message, cosmic::Theme, CosmicRenderer> {
+    canvas::Canvas::new(view)
+        .width(cosmic::iced::Length::Fill)
+        .height(cosmic::iced::Length::Fill)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::fixture::{assert_close, tree};
+
+    fn area(rect: &Rect) -> f32 {
+        rect.width * rect.height
+    }
+
+    #[test]
+    fn areas_follow_sizes_and_fill_the_bounds() {
+        let bounds = Rect { x: 10.0, y: 20.0, width: 600.0, height: 400.0 };
+        let sizes = [6000, 6000, 4000, 3000, 2000, 2000, 1000];
+        let rects = squarify(&sizes, bounds);
+        let total: u64 = sizes.iter().sum();
+
+        assert_eq!(rects.len(), sizes.len());
+        for (rect, &size) in rects.iter().zip(&sizes) {
+            assert_close(area(rect), size as f32 / total as f32 * 600.0 * 400.0);
+            assert!(rect.x >= bounds.x - 0.01 && rect.x + rect.width <= bounds.x + bounds.width + 0.01);
+            assert!(rect.y >= bounds.y - 0.01 && rect.y + rect.height <= bounds.y + bounds.height + 0.01);
+        }
+        assert_close(rects.iter().map(area).sum(), 600.0 * 400.0);
+    }
+
+    #[test]
+    fn matches_the_example_from_the_paper() {
+        let rects = squarify(&[6, 6, 4, 3, 2, 2, 1], Rect { x: 0.0, y: 0.0, width: 6.0, height: 4.0 });
+        let expected = [
+            (0.0, 0.0, 3.0, 2.0),
+            (0.0, 2.0, 3.0, 2.0),
+            (3.0, 0.0, 12.0 / 7.0, 7.0 / 3.0),
+            (3.0 + 12.0 / 7.0, 0.0, 9.0 / 7.0, 7.0 / 3.0),
+            (3.0, 7.0 / 3.0, 1.2, 5.0 / 3.0),
+            (4.2, 7.0 / 3.0, 1.2, 5.0 / 3.0),
+            (5.4, 7.0 / 3.0, 0.6, 5.0 / 3.0),
+        ];
+        for (rect, (x, y, width, height)) in rects.iter().zip(expected) {
+            assert_close(rect.x, x);
+            assert_close(rect.y, y);
+            assert_close(rect.width, width);
+            assert_close(rect.height, height);
+        }
+    }
+
+    #[test]
+    fn tiles_do_not_overlap() {
+        let rects = squarify(&[50, 30, 30, 20, 9, 5, 1, 1], Rect { x: 0.0, y: 0.0, width: 300.0, height: 120.0 });
+        for (index, a) in rects.iter().enumerate() {
+            for b in &rects[index + 1..] {
+                let overlap_x = (a.x + a.width).min(b.x + b.width) - a.x.max(b.x);
+                let overlap_y = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+                assert!(overlap_x <= 0.01 || overlap_y <= 0.01, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn nothing_to_lay_out_gives_empty_tiles() {
+        let bounds = Rect { x: 5.0, y: 5.0, width: 100.0, height: 100.0 };
+        for rect in squarify(&[0, 0], bounds) {
+            assert_eq!(area(&rect), 0.0);
+        }
+        assert!(squarify(&[], bounds).is_empty());
+        let flat = Rect { height: 0.0, ..bounds };
+        assert!(squarify(&[10, 5], flat).iter().all(|rect| area(rect) == 0.0));
+    }
+
+    #[test]
+    fn nests_folder_contents_inside_their_tile() {
+        let tree = tree();
+        let bounds = Rect { x: 0.0, y: 0.0, width: 200.0, height: 100.0 };
+        let tiles = layout(&tree, &tree.nodes[0].children, bounds);
+        let summary: Vec<(usize, usize)> = tiles.iter().map(|tile| (tile.node, tile.depth)).collect();
+        assert_eq!(summary, [(1, 0), (2, 1), (3, 1), (4, 0)]);
+
+        let (big, movie, notes) = (tiles[0].rect, tiles[1].rect, tiles[2].rect);
+        assert_close(area(&big), 0.75 * 200.0 * 100.0);
+        for inner in [movie, notes] {
+            assert!(inner.x >= big.x + PADDING && inner.x + inner.width <= big.x + big.width - PADDING + 0.01);
+            assert!(inner.y >= big.y + HEADER_HEIGHT && inner.y + inner.height <= big.y + big.height - PADDING + 0.01);
+        }
+        assert_close(area(&movie), 3.0 * area(&notes));
+
+        // The file is picked over the folder drawn around it
+        let center = (movie.x + movie.width / 2.0, movie.y + movie.height / 2.0);
+        assert_eq!(tile_at(&tiles, center.0, center.1).map(|tile| tile.node), Some(2));
+        assert_eq!(tile_at(&tiles, big.x + 1.0, big.y + 1.0).map(|tile| tile.node), Some(1));
+    }
+}
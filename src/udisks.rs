@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::fs;
+use zbus::zvariant::{OwnedObjectPath, Value};
+use zbus::{proxy, Connection};
+
+const BLOCK_DEVICES_PATH: &str = "/org/freedesktop/UDisks2/block_devices";
+
+#[proxy(
+    interface = "org.freedesktop.UDisks2.Filesystem",
+    default_service = "org.freedesktop.UDisks2"
+)]
+trait Filesystem {
+    fn mount(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<String>;
+    fn unmount(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+// UDisks2 lives on the system bus. Everything else takes the connection so it can be pointed at
+// a stand-in service instead, the tests use a peer-to-peer connection over a socket pair.
+pub async fn system_bus() -> Result<Connection, String> {
+    Connection::system().await.map_err(|e| e.to_string())
+}
+
+// Object paths only allow [A-Za-z0-9_], UDisks2 writes anything else as _XX in hex ("dm-0" -> "dm_2d0")
+pub fn object_path(device: &str) -> String {
+    let name = device.rsplit('/').next().unwrap_or(device);
+    let escaped: String = name
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() {
+                (byte as char).to_string()
+            } else {
+                format!("_{:02x}", byte)
+            }
+        })
+        .collect();
+    format!("{}/{}", BLOCK_DEVICES_PATH, escaped)
+}
+
+async fn filesystem<'a>(connection: &'a Connection, device: &str) -> Result<FilesystemProxy<'a>, String> {
+    // /dev/mapper names are symlinks to the dm-N node UDisks2 names its objects after
+    let device = fs::canonicalize(device)
+        .map(|node| node.to_string_lossy().to_string())
+        .unwrap_or_else(|_| device.to_string());
+    let path = OwnedObjectPath::try_from(object_path(&device)).map_err(|e| e.to_string())?;
+    FilesystemProxy::builder(connection)
+        .path(path)
+        .map_err(|e| e.to_string())?
+        .build()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Mounts a filesystem where UDisks2 sees fit (usually /run/media/$USER/<label>) and returns that path
+pub async fn mount(connection: &Connection, device: &str) -> Result<String, String> {
+    filesystem(connection, device)
+        .await?
+        .mount(HashMap::new())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn unmount(connection: &Connection, device: &str) -> Result<(), String> {
+    filesystem(connection, device)
+        .await?
+        .unmount(HashMap::new())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use tokio::net::UnixStream;
+    use zbus::zvariant::OwnedValue;
+    use zbus::{connection, fdo, interface, Guid};
+
+    #[test]
+    fn keeps_plain_device_names() {
+        assert_eq!(object_path("/dev/sda1"), "/org/freedesktop/UDisks2/block_devices/sda1");
+        assert_eq!(object_path("/dev/nvme0n1p2"), "/org/freedesktop/UDisks2/block_devices/nvme0n1p2");
+        assert_eq!(object_path("mmcblk0p1"), "/org/freedesktop/UDisks2/block_devices/mmcblk0p1");
+    }
+
+    #[test]
+    fn escapes_everything_but_letters_and_digits() {
+        assert_eq!(object_path("/dev/dm-0"), "/org/freedesktop/UDisks2/block_devices/dm_2d0");
+        assert_eq!(object_path("/dev/md_root"), "/org/freedesktop/UDisks2/block_devices/md_5froot");
+        assert_eq!(object_path("/dev/a.b+c"), "/org/freedesktop/UDisks2/block_devices/a_2eb_2bc");
+    }
+
+    // Stands in for UDisks2's Filesystem interface on one block device
+    struct FakeFilesystem {
+        mounted: bool,
+    }
+
+    #[interface(name = "org.freedesktop.UDisks2.Filesystem")]
+    impl FakeFilesystem {
+        fn mount(&mut self, _options: HashMap<String, OwnedValue>) -> fdo::Result<String> {
+            if self.mounted {
+                return Err(fdo::Error::Failed("Already mounted".to_string()));
+            }
+            self.mounted = true;
+            Ok("/run/media/user/Backup".to_string())
+        }
+
+        fn unmount(&mut self, _options: HashMap<String, OwnedValue>) -> fdo::Result<()> {
+            if !self.mounted {
+                return Err(fdo::Error::Failed("Not mounted".to_string()));
+            }
+            self.mounted = false;
+            Ok(())
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    // The fake service on one end of a socket pair and the client on the other, talking peer to
+    // peer with no bus in between. The service connection has to stay alive while the client is used.
+    async fn peer_connection(device: &str) -> (Connection, Connection) {
+        let (service_stream, client_stream) = UnixStream::pair().unwrap();
+        let service = connection::Builder::unix_stream(service_stream)
+            .server(Guid::generate())
+            .unwrap()
+            .p2p()
+            .serve_at(object_path(device), FakeFilesystem { mounted: false })
+            .unwrap()
+            .build();
+        // Both ends have to run the handshake at the same time
+        let service = tokio::spawn(service);
+        let client = connection::Builder::unix_stream(client_stream).p2p().build().await.unwrap();
+        (service.await.unwrap().unwrap(), client)
+    }
+
+    #[test]
+    fn mounts_and_unmounts_through_the_filesystem_interface() {
+        block_on(async {
+            let (_service, client) = peer_connection("/dev/sdz1").await;
+            assert_eq!(mount(&client, "/dev/sdz1").await, Ok("/run/media/user/Backup".to_string()));
+            assert!(mount(&client, "/dev/sdz1").await.unwrap_err().contains("Already mounted"));
+            assert_eq!(unmount(&client, "/dev/sdz1").await, Ok(()));
+            assert!(unmount(&client, "/dev/sdz1").await.unwrap_err().contains("Not mounted"));
+        });
+    }
+
+    #[test]
+    fn fails_for_a_device_without_a_filesystem_object() {
+        block_on(async {
+            let (_service, client) = peer_connection("/dev/sdz1").await;
+            assert!(mount(&client, "/dev/sdz2").await.is_err());
+            assert!(unmount(&client, "/dev/sdz2").await.is_err());
+        });
+    }
+}
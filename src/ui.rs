@@ -1,19 +1,34 @@
-use crate::files::Files;
+use crate::files::{FocusMove, Files, ListSort, SortColumn, ViewMode};
 use crate::disk::{scan_disks, Drive};
 use crate::partition::{DiskState, Message as PartitionMessage};
+use crate::filter::{FileFilter, Message as FilterMessage};
+use crate::search::{Message as SearchMessage, SearchState};
+use crate::tree::ScanTree;
+use crate::history::{History, Location};
+use crate::hotplug::watch_devices;
+use crate::udisks;
 use cosmic::iced_core::{Border, Element, Shadow, Point};
 use cosmic::iced_renderer::fallback::Renderer;
 use cosmic::iced_wgpu::Renderer as WgpuRenderer;
 use cosmic::{app::{Application, Core, Task},
              iced::widget::container::Style,
-             iced::{Alignment, Color, Length, Subscription, Event, mouse},
+             iced::{Alignment, Color, Length, Subscription, Event, keyboard, mouse},
              widget::{column, container, icon, row, scrollable, text, mouse_area}, Apply};
 use cosmic::iced_widget::button;
+use cosmic::iced_widget::scrollable::{scroll_to, AbsoluteOffset};
 use iced_tiny_skia::Renderer as SkiaRenderer;
 use crate::sizes::format_size;
+use crate::reclaim::ReclaimableEntry;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 use cosmic::iced::window::Event as WindowEvent;
 
+// How often disk throughput is refreshed in the sidebar, while a disk is expanded
+const IO_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
 fn panel_style(theme: &cosmic::Theme) -> Style {
     Style {
         border: Border {
@@ -33,6 +48,8 @@ fn panel_style(theme: &cosmic::Theme) -> Style {
 #[derive(Debug, Clone)]
 enum FilesState {
     None,
+    // The partition being browsed was unmounted or its device removed
+    Removed(String),
     Loading(String),
     Ready(Files, Option<crate::crawler::FileEntry>),
 }
@@ -46,12 +63,119 @@ pub struct CosmicDust {
     verbose: bool,
     cursor_position: Point,
     window_size: cosmic::iced::Size,
+    reclaim_reports: HashMap<String, Vec<ReclaimableEntry>>,
+    show_reclaim_report: bool,
+    file_filter: FileFilter,
+    search: SearchState,
+    // Tree of the whole partition, kept while browsing its subfolders so search can cover all of it
+    partition_tree: Option<Arc<ScanTree>>,
+    // Total of every folder crawled on the partition, so breadcrumbs above a subfolder have sizes
+    // without a crawl of the whole partition
+    folder_sizes: HashMap<PathBuf, u64>,
+    pending_highlight: Option<PathBuf>,
+    view_mode: ViewMode,
+    list_sort: ListSort,
+    // Folders opened in the tree view, by path so they stay open across crawls
+    tree_expanded: HashSet<PathBuf>,
+    history: History,
+    // Where the grid is scrolled to, and where to scroll it once the next folder loads
+    grid_scroll: f32,
+    pending_scroll: Option<f32>,
+}
+
+impl CosmicDust {
+    // Images and snaps are files on another partition, counting them would count that space twice
+    fn update_totals(&mut self, drives: &[Drive]) {
+        self.total_space = drives.iter().map(|d| match d {
+            Drive::Local(disk) if disk.backing_file.is_some() => 0,
+            Drive::Local(disk) => disk.total_space,
+            Drive::Network(net) => net.total_space,
+        }).sum();
+        self.used_space = drives.iter().map(|d| match d {
+            Drive::Local(disk) if disk.backing_file.is_some() => 0,
+            Drive::Local(disk) => disk.filesystems().iter().map(|p| p.used_space).sum::<u64>(),
+            Drive::Network(net) => net.used_space,
+        }).sum();
+    }
+
+    // The folder being browsed as it looks right now, None while nothing is loaded
+    fn current_location(&self) -> Option<Location> {
+        match &self.files_state {
+            FilesState::Ready(files, hovered) => Some(Location {
+                mount_point: files.mount_point.clone(),
+                path: files.current_path.clone(),
+                scroll: self.grid_scroll,
+                selected: hovered.as_ref().map(|entry| entry.path.clone()).or_else(|| files.highlighted.clone()),
+            }),
+            _ => None,
+        }
+    }
+
+    // Called before leaving the current folder for a new one
+    fn remember_location(&mut self) {
+        if let Some(location) = self.current_location() {
+            self.history.visit(location);
+        }
+    }
+
+    // Load a folder from the history and put the scroll position and marked tile back
+    fn restore(&mut self, location: Location) -> Task<Message> {
+        let same_partition = matches!(&self.files_state, FilesState::Ready(files, _) if files.mount_point == location.mount_point);
+        if !same_partition {
+            self.partition_tree = None;
+            self.folder_sizes.clear();
+            self.search = SearchState::default();
+        }
+        self.pending_highlight = location.selected;
+        self.pending_scroll = Some(location.scroll);
+        self.files_state = FilesState::Loading(location.path.clone());
+        let verbose = self.verbose;
+        Task::perform(
+            async move { Files::load(location.mount_point, location.path, verbose) },
+            |files| cosmic::Action::App(Message::FilesLoaded(files)),
+        )
+    }
+
+    // Load the folder holding a path with its tile highlighted and scrolled into view
+    fn reveal(&mut self, mount_point: String, path: PathBuf) -> Task<Message> {
+        self.remember_location();
+        let folder = path.parent()
+            .filter(|parent| parent.starts_with(&mount_point))
+            .map(|parent| parent.to_string_lossy().to_string())
+            .unwrap_or(mount_point.clone());
+
+        // Make sure the filter can't hide the tile we're jumping to
+        self.file_filter.update(FilterMessage::Clear);
+        self.pending_highlight = Some(path);
+        self.files_state = FilesState::Loading(folder.clone());
+        let verbose = self.verbose;
+        Task::perform(
+            async move { Files::load(mount_point, folder, verbose) },
+            |files| cosmic::Action::App(Message::FilesLoaded(files)),
+        )
+    }
+
+    // Space left for the files grid once the surrounding rows and padding are taken out
+    fn files_area_size(&self) -> (f32, f32) {
+        let top_row_height = 30.0;
+        let filter_row_height = 30.0;
+        let bottom_row_height = 30.0;
+        let column_spacing = 8.0;
+        let padding = 8.0 * 2.0;
+        let available_height = self.window_size.height - top_row_height - filter_row_height - bottom_row_height - column_spacing - padding;
+        let available_width = self.window_size.width - padding;
+        (available_height, available_width)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ScanUpdate(Vec<Drive>),
+    DevicesChanged,
+    DrivesChanged(Vec<Drive>),
     Disk(PartitionMessage),
+    Filter(FilterMessage),
+    Search(SearchMessage),
     FilesLoaded(Files),
     CrawlSubfolder(String),
     HoverUpdate(Option<crate::crawler::FileEntry>),
@@ -60,6 +184,23 @@ pub enum Message {
     CursorMoved(Point),
     Click,
     WindowResized(cosmic::iced::Size),
+    ToggleReclaimReport,
+    // UDisks2 replies, with the device they're about
+    Mounted(String, Result<String, String>),
+    Unmounted(String, Result<(), String>),
+    // Time to sample disk throughput
+    IoTick,
+    SetViewMode(ViewMode),
+    // Column header clicked in the list view
+    SortList(SortColumn),
+    // Open or close a folder in the tree view
+    ToggleTreeNode(PathBuf),
+    GridScrolled(f32),
+    GoBack,
+    GoForward,
+    // Keyboard navigation of the grid
+    MoveFocus(FocusMove),
+    OpenFocused,
 }
 
 impl Application for CosmicDust {
@@ -92,43 +233,269 @@ impl Application for CosmicDust {
                 verbose,
                 cursor_position: Point::new(0.0, 0.0),
                 window_size: default_size,
+                reclaim_reports: HashMap::new(),
+                show_reclaim_report: false,
+                file_filter: FileFilter::default(),
+                search: SearchState::default(),
+                partition_tree: None,
+                folder_sizes: HashMap::new(),
+                pending_highlight: None,
+                view_mode: ViewMode::default(),
+                list_sort: ListSort::default(),
+                tree_expanded: HashSet::new(),
+                history: History::default(),
+                grid_scroll: 0.0,
+                pending_scroll: None,
             },
             scan_task,
         )
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        cosmic::iced::event::listen_raw(|event, _status, _context| {
+        let events = cosmic::iced::event::listen_raw(|event, status, _context| {
+            // Keys already taken by a text field, such as the search box, aren't for the grid
+            let unhandled = status == cosmic::iced::event::Status::Ignored;
             match event {
                 Event::Mouse(mouse::Event::CursorMoved { position }) => Some(Message::CursorMoved(position)),
                 Event::Window(WindowEvent::Resized(size)) => {
                     Some(Message::WindowResized(size))
                 }
+                // Side buttons and Alt+arrows, like a browser. Not while typing, leaving the folder
+                // would throw away the query.
+                Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Back)) => Some(Message::GoBack),
+                Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Forward)) => Some(Message::GoForward),
+                Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(keyboard::key::Named::ArrowLeft), modifiers, .. })
+                    if unhandled && modifiers.alt() =>
+                {
+                    Some(Message::GoBack)
+                }
+                Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(keyboard::key::Named::ArrowRight), modifiers, .. })
+                    if unhandled && modifiers.alt() =>
+                {
+                    Some(Message::GoForward)
+                }
+                Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(named), modifiers, .. })
+                    if unhandled && !modifiers.alt() =>
+                {
+                    match named {
+                        keyboard::key::Named::ArrowLeft => Some(Message::MoveFocus(FocusMove::Left)),
+                        keyboard::key::Named::ArrowRight => Some(Message::MoveFocus(FocusMove::Right)),
+                        keyboard::key::Named::ArrowUp => Some(Message::MoveFocus(FocusMove::Up)),
+                        keyboard::key::Named::ArrowDown => Some(Message::MoveFocus(FocusMove::Down)),
+                        keyboard::key::Named::Enter => Some(Message::OpenFocused),
+                        keyboard::key::Named::Backspace => Some(Message::UpButtonClicked),
+                        _ => None,
+                    }
+                }
                 _ => None,
             }
-        })
+        });
+
+        // udev and mount table changes, watched from a dedicated thread since both are blocking fds
+        let verbose = self.verbose;
+        let hotplug = Subscription::run_with_id(
+            "hotplug",
+            cosmic::iced::stream::channel(1, move |output| async move {
+                std::thread::spawn(move || {
+                    let mut output = output;
+                    watch_devices(verbose, || match output.try_send(Message::DevicesChanged) {
+                        Ok(()) => true,
+                        // A full channel means a rescan is already queued
+                        Err(e) => !e.is_disconnected(),
+                    });
+                });
+                std::future::pending::<()>().await
+            }),
+        );
+
+        let mut subscriptions = vec![events, hotplug];
+        // No point waking up every second while every disk is collapsed
+        if self.disk_state.watching_io() {
+            subscriptions.push(cosmic::iced::time::every(IO_SAMPLE_INTERVAL).map(|_| Message::IoTick));
+        }
+
+        Subscription::batch(subscriptions)
     }
 
     fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
         match message {
             Message::ScanUpdate(drives) => {
-                self.total_space = drives.iter().map(|d| match d {
-                    Drive::Local(disk) => disk.total_space,
-                    Drive::Network(net) => net.total_space,
-                }).sum();
-                self.used_space = drives.iter().map(|d| match d {
-                    Drive::Local(disk) => disk.partitions.iter().map(|p| p.used_space).sum::<u64>(),
-                    Drive::Network(net) => net.used_space,
-                }).sum();
+                self.update_totals(&drives);
+                let reclaimable = std::mem::take(&mut self.disk_state.reclaimable);
+                let mounted_by_us = std::mem::take(&mut self.disk_state.mounted_by_us);
                 self.disk_state = DiskState::new(drives);
+                self.disk_state.reclaimable = reclaimable;
+                self.disk_state.mounted_by_us = mounted_by_us;
                 self.files_state = FilesState::None;
                 Task::none()
             }
+            Message::DevicesChanged => Task::perform(scan_disks(), |drives| cosmic::Action::App(Message::DrivesChanged(drives))),
+            Message::DrivesChanged(drives) => {
+                self.update_totals(&drives);
+                self.disk_state.update_drives(drives);
+
+                // Let the user know if the partition they're browsing has disappeared
+                let browsed = match &self.files_state {
+                    FilesState::Ready(files, _) => Some(files.mount_point.clone()),
+                    _ => None,
+                };
+                if let Some(mount_point) = browsed {
+                    let still_mounted = self.disk_state.drives.iter()
+                        .any(|drive| drive.mount_points().contains(&mount_point.as_str()));
+                    if !still_mounted {
+                        self.partition_tree = None;
+                        self.folder_sizes.clear();
+                        self.search = SearchState::default();
+                        self.files_state = FilesState::Removed(mount_point);
+                    }
+                }
+                Task::none()
+            }
+            Message::Disk(PartitionMessage::Retry) => self.update(Message::DevicesChanged),
             Message::Disk(PartitionMessage::ToggleDisk(index)) => {
                 self.disk_state.toggle(index);
                 Task::none()
             }
+            Message::Disk(PartitionMessage::ToggleDetails(device)) => {
+                self.disk_state.toggle_details(device);
+                Task::none()
+            }
+            Message::Disk(PartitionMessage::ToggleImages) => {
+                self.disk_state.images_expanded = !self.disk_state.images_expanded;
+                Task::none()
+            }
+            Message::Disk(PartitionMessage::RevealFile(path)) => {
+                // The innermost mount containing the file, images themselves don't count
+                let host = self.disk_state.drives.iter()
+                    .filter(|drive| !matches!(drive, Drive::Local(disk) if disk.backing_file.is_some()))
+                    .flat_map(|drive| drive.mount_points())
+                    .filter(|mount_point| path.starts_with(mount_point))
+                    .max_by_key(|mount_point| mount_point.len())
+                    .map(str::to_string);
+                match host {
+                    Some(mount_point) => {
+                        self.partition_tree = None;
+                        self.folder_sizes.clear();
+                        self.search = SearchState::default();
+                        self.reveal(mount_point, path)
+                    }
+                    None => {
+                        if self.verbose {
+                            println!("No scanned partition holds {}", path.display());
+                        }
+                        Task::none()
+                    }
+                }
+            }
+            Message::Disk(PartitionMessage::Mount(device)) => {
+                self.disk_state.mount_error = None;
+                let target = device.clone();
+                Task::perform(
+                    async move {
+                        let connection = udisks::system_bus().await?;
+                        udisks::mount(&connection, &target).await
+                    },
+                    move |result| cosmic::Action::App(Message::Mounted(device.clone(), result)),
+                )
+            }
+            Message::Disk(PartitionMessage::Unmount(device)) => {
+                self.disk_state.mount_error = None;
+                let target = device.clone();
+                Task::perform(
+                    async move {
+                        let connection = udisks::system_bus().await?;
+                        udisks::unmount(&connection, &target).await
+                    },
+                    move |result| cosmic::Action::App(Message::Unmounted(device.clone(), result)),
+                )
+            }
+            Message::SetViewMode(mode) => {
+                self.view_mode = mode;
+                Task::none()
+            }
+            Message::MoveFocus(direction) => {
+                // Other views lay entries out differently, arrows only follow the grid
+                if self.view_mode != ViewMode::Grid || self.search.results.is_some() || self.show_reclaim_report {
+                    return Task::none();
+                }
+                let (available_height, available_width) = self.files_area_size();
+                let FilesState::Ready(files, hovered) = &mut self.files_state else {
+                    return Task::none();
+                };
+                // The bottom row describes the focused tile just like a hovered one
+                *hovered = files.move_focus(&self.file_filter, direction, available_width);
+                match files.scroll_to_focus(&self.file_filter, available_width, available_height, self.grid_scroll) {
+                    Some(y) => scroll_to(Files::grid_id(), AbsoluteOffset { x: 0.0, y }),
+                    None => Task::none(),
+                }
+            }
+            Message::OpenFocused => {
+                let folder = match &self.files_state {
+                    FilesState::Ready(files, _) => files.focused.as_ref()
+                        .filter(|path| files.entries.iter().any(|entry| &entry.path == *path && entry.is_dir))
+                        .map(|path| path.to_string_lossy().to_string()),
+                    _ => None,
+                };
+                match folder {
+                    Some(folder) => self.update(Message::CrawlSubfolder(folder)),
+                    None => Task::none(),
+                }
+            }
+            Message::GridScrolled(offset) => {
+                self.grid_scroll = offset;
+                Task::none()
+            }
+            Message::GoBack => match self.current_location() {
+                Some(current) => match self.history.back(current) {
+                    Some(location) => self.restore(location),
+                    None => Task::none(),
+                },
+                None => Task::none(),
+            },
+            Message::GoForward => match self.current_location() {
+                Some(current) => match self.history.forward(current) {
+                    Some(location) => self.restore(location),
+                    None => Task::none(),
+                },
+                None => Task::none(),
+            },
+            Message::SortList(column) => {
+                self.list_sort = self.list_sort.toggle(column);
+                Task::none()
+            }
+            Message::ToggleTreeNode(path) => {
+                if !self.tree_expanded.remove(&path) {
+                    self.tree_expanded.insert(path);
+                }
+                Task::none()
+            }
+            Message::IoTick => {
+                self.disk_state.sample_io();
+                Task::none()
+            }
+            Message::Mounted(device, Ok(mount_point)) => {
+                self.disk_state.mounted_by_us.insert(device);
+                Task::batch(vec![
+                    self.update(Message::DevicesChanged),
+                    self.update(Message::Disk(PartitionMessage::SelectPartition(mount_point))),
+                ])
+            }
+            Message::Unmounted(device, Ok(())) => {
+                self.disk_state.mounted_by_us.remove(&device);
+                self.update(Message::DevicesChanged)
+            }
+            Message::Mounted(device, Err(e)) | Message::Unmounted(device, Err(e)) => {
+                if self.verbose {
+                    println!("UDisks2 request for {} failed: {}", device, e);
+                }
+                self.disk_state.mount_error = Some(format!("{}: {}", device, e));
+                Task::none()
+            }
             Message::Disk(PartitionMessage::SelectPartition(mount)) => {
+                self.remember_location();
+                self.partition_tree = None;
+                self.folder_sizes.clear();
+                self.search = SearchState::default();
                 self.files_state = FilesState::Loading(mount.clone());
                 let verbose = self.verbose;
                 Task::perform(
@@ -136,11 +503,36 @@ impl Application for CosmicDust {
                     |files| cosmic::Action::App(Message::FilesLoaded(files)),
                 )
             }
-            Message::FilesLoaded(files) => {
+            Message::FilesLoaded(mut files) => {
+                // A crawl from the mount point covers the whole partition, keep its report and tree around
+                if files.current_path == files.mount_point {
+                    let total = files.reclaimable.iter().map(|entry| entry.size).sum();
+                    self.disk_state.reclaimable.insert(files.mount_point.clone(), total);
+                    self.reclaim_reports.insert(files.mount_point.clone(), files.reclaimable.clone());
+                    self.partition_tree = Some(files.tree.clone());
+                }
+                if !files.not_responding {
+                    self.folder_sizes.insert(PathBuf::from(&files.current_path), files.tree.nodes[0].size);
+                }
+                self.show_reclaim_report = false;
+
+                // Bring a search match into view once its folder has loaded
+                files.highlighted = self.pending_highlight.take();
+                let (_, available_width) = self.files_area_size();
+                let scroll = self.pending_scroll.take().or_else(|| {
+                    files.highlighted.as_ref()
+                        .and_then(|path| files.scroll_offset_for(&self.file_filter, path, available_width))
+                });
+                self.grid_scroll = scroll.unwrap_or(0.0);
+
                 self.files_state = FilesState::Ready(files, None);
-                Task::none()
+                match scroll {
+                    Some(y) => scroll_to(Files::grid_id(), AbsoluteOffset { x: 0.0, y }),
+                    None => Task::none(),
+                }
             }
             Message::CrawlSubfolder(subfolder) => {
+                self.remember_location();
                 let mount_point = if let FilesState::Ready(files, _) = &self.files_state {
                     files.mount_point.clone()
                 } else {
@@ -186,6 +578,8 @@ impl Application for CosmicDust {
                     return Task::none();
                 }
 
+                self.remember_location();
+
                 self.files_state = FilesState::Loading(mount_point.clone());
                 let verbose = self.verbose;
                 Task::perform(
@@ -205,6 +599,31 @@ impl Application for CosmicDust {
                 self.window_size = size;
                 Task::none()
             }
+            Message::Search(SearchMessage::Select(path)) => {
+                self.search.update(SearchMessage::Close, None);
+                let mount_point = if let FilesState::Ready(files, _) = &self.files_state {
+                    files.mount_point.clone()
+                } else {
+                    return Task::none();
+                };
+                self.reveal(mount_point, path)
+            }
+            Message::Search(message) => {
+                let tree = self.partition_tree.clone().or_else(|| match &self.files_state {
+                    FilesState::Ready(files, _) => Some(files.tree.clone()),
+                    _ => None,
+                });
+                self.search.update(message, tree.as_deref());
+                Task::none()
+            }
+            Message::Filter(message) => {
+                self.file_filter.update(message);
+                Task::none()
+            }
+            Message::ToggleReclaimReport => {
+                self.show_reclaim_report = !self.show_reclaim_report;
+                Task::none()
+            }
         }
     }
 
@@ -241,13 +660,24 @@ impl Application for CosmicDust {
             )
                 .align_y(Alignment::Center)
                 .height(Length::Fill),
+            FilesState::Removed(mount) => container(
+                column()
+                    .spacing(8)
+                    .push(icon::from_name("drive-removable-media").size(48))
+                    .push(text(format!("{} is no longer available", mount)).size(16.0))
+                    .push(text("It was unmounted or its device was removed. Pick another partition to keep browsing."))
+                    .align_x(Alignment::Center)
+                    .width(Length::Fill)
+            )
+                .align_y(Alignment::Center)
+                .height(Length::Fill),
             FilesState::Loading(mount) => container(
                 column()
                     .spacing(8)
                     .push(
                         text(self.disk_state.drives.iter().find_map(|drive| match drive {
-                            Drive::Local(disk) if !disk.is_cdrom => disk.partitions.iter()
-                                .find(|p| &p.mount_point == mount)
+                            Drive::Local(disk) if !disk.is_cdrom => disk.filesystems().into_iter()
+                                .find(|p| p.mount_points().contains(&mount.as_str()))
                                 .map(|p| format!("{} - ({})", mount, p.file_system)),
                             _ => Some(mount.clone()),
                         }).unwrap_or(mount.clone()))
@@ -264,6 +694,21 @@ impl Application for CosmicDust {
                             .height(Length::Fill)
                     )
             ),
+            FilesState::Ready(files, _) if files.not_responding => container(
+                column()
+                    .spacing(8)
+                    .push(icon::from_name("network-error").size(48))
+                    .push(text(format!("{} is not responding", files.current_path)).size(16.0))
+                    .push(
+                        button(text("Retry"))
+                            .on_press(Message::CrawlSubfolder(files.current_path.clone()))
+                            .padding([4, 8])
+                    )
+                    .align_x(Alignment::Center)
+                    .width(Length::Fill)
+            )
+                .align_y(Alignment::Center)
+                .height(Length::Fill),
             FilesState::Ready(files, hovered) => {
                 let up_button: Element<Self::Message, cosmic::Theme, Renderer<WgpuRenderer, SkiaRenderer>> = if files.current_path != files.mount_point {
                     button(
@@ -283,46 +728,124 @@ impl Application for CosmicDust {
                         .into()
                 };
 
+                let report = self.reclaim_reports.get(&files.mount_point);
+                let report_button: Element<Self::Message, cosmic::Theme, Renderer<WgpuRenderer, SkiaRenderer>> = match report {
+                    Some(report) if !report.is_empty() => {
+                        let total: u64 = report.iter().map(|entry| entry.size).sum();
+                        button(
+                            row()
+                                .push(icon::from_name("user-trash-full-symbolic").size(16))
+                                .push(text(format!("Reclaimable: {}", format_size(total))))
+                                .spacing(4)
+                                .align_y(Alignment::Center)
+                        )
+                            .height(Length::Fixed(30.0))
+                            .on_press(Message::ToggleReclaimReport)
+                            .into()
+                    }
+                    _ => container(text("")).into(),
+                };
+
+                let mode_button = |mode: ViewMode, icon_name: &'static str| -> Element<Self::Message, cosmic::Theme, Renderer<WgpuRenderer, SkiaRenderer>> {
+                    let mode_button = button(icon::from_name(icon_name).size(16))
+                        .width(Length::Fixed(30.0))
+                        .height(Length::Fixed(30.0));
+                    // The active mode has no action, which also draws it as selected
+                    if self.view_mode == mode {
+                        mode_button.into()
+                    } else {
+                        mode_button.on_press(Message::SetViewMode(mode)).into()
+                    }
+                };
+
+                let history_button = |icon_name: &'static str, message: Option<Message>| -> Element<Self::Message, cosmic::Theme, Renderer<WgpuRenderer, SkiaRenderer>> {
+                    let history_button = button(icon::from_name(icon_name).size(16))
+                        .width(Length::Fixed(30.0))
+                        .height(Length::Fixed(30.0));
+                    match message {
+                        Some(message) => history_button.on_press(message).into(),
+                        None => history_button.into(),
+                    }
+                };
+
                 let top_row = row()
+                    .push(history_button("go-previous-symbolic", self.history.can_go_back().then_some(Message::GoBack)))
+                    .push(history_button("go-next-symbolic", self.history.can_go_forward().then_some(Message::GoForward)))
                     .push(
-                        text(&files.current_path)
-                            .size(16.0)
+                        container(files.breadcrumbs(self.partition_tree.as_deref(), &self.folder_sizes))
                             .width(Length::Fill)
                             .align_y(Alignment::Center)
                     )
+                    .push(mode_button(ViewMode::Grid, "view-grid-symbolic"))
+                    .push(mode_button(ViewMode::List, "view-list-symbolic"))
+                    .push(mode_button(ViewMode::Tree, "view-list-tree-symbolic"))
+                    .push(mode_button(ViewMode::Treemap, "view-app-grid-symbolic"))
+                    .push(mode_button(ViewMode::Sunburst, "media-optical-symbolic"))
+                    .push(self.search.input_view().map(Message::Search))
+                    .push(report_button)
                     .push(up_button)
                     .spacing(8)
                     .height(Length::Fixed(30.0));
 
-                let window_height = self.window_size.height;
-                let window_width = self.window_size.width;
-                let top_row_height = 30.0;
-                let bottom_row_height = 30.0;
-                let column_spacing = 8.0;
-                let padding = 8.0 * 2.0;
-                let available_height = window_height - top_row_height - bottom_row_height - column_spacing - padding;
-                let available_width = window_width - padding;
+                let filter_bar = self.file_filter.view().map(Message::Filter);
+
+                let (available_height, available_width) = self.files_area_size();
 
-                let files_area = container(files.view(available_height, available_width))
+                let files_view = match report {
+                    _ if self.search.results.is_some() => self.search.results_view().map(Message::Search),
+                    Some(report) if self.show_reclaim_report => Files::reclaim_view(report, available_width),
+                    _ => match self.view_mode {
+                        ViewMode::Grid => files.view(&self.file_filter, available_height, available_width),
+                        ViewMode::Treemap => files.treemap_view(&self.file_filter),
+                        ViewMode::Sunburst => files.sunburst_view(&self.file_filter),
+                        ViewMode::List => files.list_view(&self.file_filter, self.list_sort, available_width),
+                        ViewMode::Tree => files.tree_view(&self.file_filter, &self.tree_expanded, available_width),
+                    },
+                };
+                let files_area = container(files_view)
                     .width(Length::Fill)
                     .height(Length::Fill);
 
-                let bottom_row = container(
-                    text(
-                        hovered.as_ref().map_or(String::new(), |entry| {
-                            let file_name = entry.path.file_name().unwrap_or_default().to_string_lossy();
-                            format!("{} ({})", file_name, format_size(entry.size))
-                        })
+                let ((visible_count, visible_size), (hidden_count, hidden_size)) = files.filter_stats(&self.file_filter);
+                let counts = if self.file_filter.is_active() {
+                    format!(
+                        "Showing {} ({}), hidden {} ({})",
+                        visible_count,
+                        format_size(visible_size),
+                        hidden_count,
+                        format_size(hidden_size)
                     )
-                        .size(16.0)
-                        .align_x(Alignment::Center)
-                        .width(Length::Fill)
+                } else {
+                    format!("{} items ({})", visible_count, format_size(visible_size))
+                };
+
+                let bottom_row = container(
+                    row()
+                        .push(
+                            text(
+                                hovered.as_ref().map_or(String::new(), |entry| {
+                                    let file_name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+                                    match &entry.reclaimable {
+                                        Some(description) => format!("{} ({}) - Potentially reclaimable: {}", file_name, format_size(entry.size), description),
+                                        None if entry.swap => format!("{} ({}) - Active swap file", file_name, format_size(entry.size)),
+                                        None => format!("{} ({})", file_name, format_size(entry.size)),
+                                    }
+                                })
+                            )
+                                .size(16.0)
+                                .align_x(Alignment::Center)
+                                .width(Length::Fill)
+                        )
+                        .push(text(counts).size(14.0))
+                        .spacing(8)
+                        .align_y(Alignment::Center)
                 )
                     .height(Length::Fixed(30.0));
 
                 container(
                     column()
                         .push(top_row)
+                        .push(filter_bar)
                         .push(files_area)
                         .push(bottom_row)
                         .spacing(8)
@@ -0,0 +1,89 @@
+use crate::config::home_dir;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Folder icon for each key of user-dirs.dirs
+const FOLDER_ICONS: [(&str, &str); 8] = [
+    ("XDG_DESKTOP_DIR", "user-desktop"),
+    ("XDG_DOCUMENTS_DIR", "folder-documents"),
+    ("XDG_DOWNLOAD_DIR", "folder-download"),
+    ("XDG_MUSIC_DIR", "folder-music"),
+    ("XDG_PICTURES_DIR", "folder-pictures"),
+    ("XDG_PUBLICSHARE_DIR", "folder-publicshare"),
+    ("XDG_TEMPLATES_DIR", "folder-templates"),
+    ("XDG_VIDEOS_DIR", "folder-videos"),
+];
+
+// XDG_DOWNLOAD_DIR="$HOME/Downloads", see xdg-user-dirs-update(1). A folder set to $HOME itself
+// is how a special folder gets turned off, so those are left out.
+pub fn parse_user_dirs(contents: &str, home: &Path) -> Vec<(PathBuf, &'static str)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let (_, icon) = FOLDER_ICONS.iter().find(|(name, _)| *name == key.trim())?;
+            let value = value.trim().trim_matches('"');
+            let path = match value.strip_prefix("$HOME") {
+                Some(rest) => home.join(rest.trim_start_matches('/')),
+                None => PathBuf::from(value),
+            };
+            (path.is_absolute() && path != home).then_some((path, *icon))
+        })
+        .collect()
+}
+
+// Home and the special folders in it with their icons, empty without a $HOME
+pub fn special_folders() -> Vec<(PathBuf, &'static str)> {
+    let Some(home) = home_dir() else {
+        return Vec::new();
+    };
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| home.join(".config"));
+
+    let mut folders = fs::read_to_string(config_home.join("user-dirs.dirs"))
+        .map(|contents| parse_user_dirs(&contents, &home))
+        .unwrap_or_default();
+    folders.push((home, "user-home"));
+    folders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_special_folders_with_their_icons() {
+        let contents = "\
+# This file is written by xdg-user-dirs-update
+XDG_DESKTOP_DIR=\"$HOME/Desktop\"
+XDG_DOWNLOAD_DIR=\"$HOME/Downloads\"
+  XDG_MUSIC_DIR = \"/srv/music\"
+XDG_VIDEOS_DIR=$HOME/Videos
+";
+        let folders = parse_user_dirs(contents, Path::new("/home/user"));
+        assert_eq!(folders, [
+            (PathBuf::from("/home/user/Desktop"), "user-desktop"),
+            (PathBuf::from("/home/user/Downloads"), "folder-download"),
+            (PathBuf::from("/srv/music"), "folder-music"),
+            (PathBuf::from("/home/user/Videos"), "folder-videos"),
+        ]);
+    }
+
+    #[test]
+    fn leaves_out_disabled_relative_and_unknown_entries() {
+        let contents = "\
+XDG_TEMPLATES_DIR=\"$HOME/\"
+XDG_PUBLICSHARE_DIR=\"$HOME\"
+XDG_PICTURES_DIR=\"Pictures\"
+XDG_PROJECTS_DIR=\"$HOME/Projects\"
+# XDG_DOCUMENTS_DIR=\"$HOME/Documents\"
+XDG_DOCUMENTS_DIR
+";
+        assert!(parse_user_dirs(contents, Path::new("/home/user")).is_empty());
+    }
+}